@@ -3,6 +3,7 @@ use solana_sdk::pubkey::Pubkey;
 pub struct Base {
     pub lookup_table_account: Option<String>,
     pub vault: Vault,
+    pub mint_program: MintProgram,
     // program_id: Pubkey,
     // id: Pubkey,
 }
@@ -11,6 +12,11 @@ pub struct Vault {
     pub a: Pubkey,
     pub b: Pubkey,
 }
+/// Token program owning each mint, spl_token or spl_token_2022.
+pub struct MintProgram {
+    pub a: Pubkey,
+    pub b: Pubkey,
+}
 pub struct ClmmKeys {
     pub base: Base,
 }
@@ -0,0 +1,23 @@
+use crate::common::pubkey::MEMO_PROGRAM_ID;
+use crate::common::tx_tool::tx_type::InstructionType;
+use crate::raydium::tpe::MakeInstructionsResult;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+
+pub fn make_memo_instruction(memo: &str, signers: &[Pubkey]) -> Instruction {
+    Instruction::new_with_bytes(
+        Pubkey::from_str_const(MEMO_PROGRAM_ID),
+        memo.as_bytes(),
+        signers
+            .iter()
+            .map(|x| AccountMeta::new_readonly(*x, true))
+            .collect(),
+    )
+}
+
+pub fn make_memo_instructions(memo: &str, signers: &[Pubkey]) -> MakeInstructionsResult {
+    MakeInstructionsResult::new(
+        vec![make_memo_instruction(memo, signers)],
+        vec![InstructionType::Memo],
+    )
+}
@@ -1,4 +1,6 @@
 pub mod tx_tool;
 pub mod owner;
+pub mod memo;
 pub mod pubkey;
 pub mod pda;
+pub mod util;
@@ -1,10 +1,20 @@
+use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::Keypair;
+use solana_sdk::signature::{Keypair, Signature};
 use solana_sdk::signer::Signer;
 
+/// Signer living outside the process: custody services, KMS, hardware wallets.
+#[async_trait]
+pub trait RemoteSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, String>;
+}
+
 pub enum OwnerKind {
     Keypair(Keypair),
     Pubkey(Pubkey),
+    Signer(Box<dyn Signer + Send + Sync>),
+    RemoteSigner(Box<dyn RemoteSigner>),
 }
 pub struct Owner {
     inner: OwnerKind,
@@ -22,6 +32,8 @@ impl Owner {
         match &self.inner {
             OwnerKind::Keypair(x) => x.pubkey(),
             OwnerKind::Pubkey(x) => *x,
+            OwnerKind::Signer(x) => x.pubkey(),
+            OwnerKind::RemoteSigner(x) => x.pubkey(),
         }
     }
     pub fn keypair(&self) -> Option<&Keypair> {
@@ -30,4 +42,35 @@ impl Owner {
             _ => None,
         }
     }
+    /// Local signer, `None` for remote signers and bare pubkeys.
+    pub fn signer(&self) -> Option<&dyn Signer> {
+        match &self.inner {
+            OwnerKind::Keypair(x) => Some(x),
+            OwnerKind::Signer(x) => Some(x.as_ref()),
+            _ => None,
+        }
+    }
+    pub fn can_sign(&self) -> bool {
+        !matches!(self.inner, OwnerKind::Pubkey(_))
+    }
+    pub async fn sign_message(&self, message: &[u8]) -> Result<Signature, String> {
+        match &self.inner {
+            OwnerKind::Keypair(x) => x.try_sign_message(message).map_err(|e| e.to_string()),
+            OwnerKind::Signer(x) => x.try_sign_message(message).map_err(|e| e.to_string()),
+            OwnerKind::RemoteSigner(x) => x.sign_message(message).await,
+            OwnerKind::Pubkey(x) => Err(format!("Owner {} can not sign", x)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn keypair_owner() -> Owner {
+    Owner::new(OwnerKind::Keypair(Keypair::new()))
+}
+
+/// Pubkey-only owner and the keypair signing for it outside the builder.
+#[cfg(test)]
+pub(crate) fn offline_owner() -> (Owner, Keypair) {
+    let keypair = Keypair::new();
+    (Owner::new(OwnerKind::Pubkey(keypair.pubkey())), keypair)
 }
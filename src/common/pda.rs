@@ -1,5 +1,5 @@
+use crate::common::pubkey::ASSOCIATED_TOKEN_PROGRAM_ID;
 use solana_sdk::pubkey::Pubkey;
-use std::str::FromStr;
 
 pub fn get_ata_address(owner: &Pubkey, mint: &Pubkey, program_id: Option<&Pubkey>) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -11,6 +11,6 @@ pub fn get_ata_address(owner: &Pubkey, mint: &Pubkey, program_id: Option<&Pubkey
                 .as_slice(),
             mint.to_bytes().as_slice(),
         ],
-        &Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL").unwrap(),
+        &Pubkey::from_str_const(ASSOCIATED_TOKEN_PROGRAM_ID),
     )
 }
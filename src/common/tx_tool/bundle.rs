@@ -0,0 +1,202 @@
+use crate::common::tx_tool::unsigned_tx::UnsignedTransaction;
+use crate::common::tx_tool::{TxBuilder, TxTipConfig};
+use async_trait::async_trait;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+#[async_trait]
+pub trait BundleSender: Send + Sync {
+    /// Submits signed transactions as one atomic bundle, returns the bundle id.
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String, String>;
+}
+
+/// Round-robin over the tip accounts of a block engine.
+pub struct TipAccounts {
+    accounts: Vec<Pubkey>,
+    next: AtomicUsize,
+}
+
+impl TipAccounts {
+    pub fn new(accounts: Vec<Pubkey>) -> Result<Self, String> {
+        if accounts.is_empty() {
+            return Err("Tip accounts are empty".to_string());
+        }
+        Ok(Self {
+            accounts,
+            next: AtomicUsize::new(0),
+        })
+    }
+    pub fn accounts(&self) -> &Vec<Pubkey> {
+        &self.accounts
+    }
+    pub fn next_account(&self) -> Pubkey {
+        let i = self.next.fetch_add(1, Ordering::Relaxed);
+        self.accounts[i % self.accounts.len()]
+    }
+    pub fn next_tip_config(&self, lamports: u64) -> TxTipConfig {
+        TxTipConfig::new(self.next_account(), lamports)
+    }
+}
+
+pub struct BundleBuilder<'a> {
+    max_transactions: usize,
+    transactions: Vec<TxBuilder<'a>>,
+}
+
+impl<'a> BundleBuilder<'a> {
+    pub fn new(max_transactions: usize) -> Self {
+        Self {
+            max_transactions: max_transactions.min(MAX_BUNDLE_TRANSACTIONS),
+            transactions: vec![],
+        }
+    }
+    pub fn add_transaction(&mut self, tx_builder: TxBuilder<'a>) -> Result<(), String> {
+        if self.transactions.len() >= self.max_transactions {
+            return Err(format!(
+                "Bundle is limited to {} transactions",
+                self.max_transactions
+            ));
+        }
+        self.transactions.push(tx_builder);
+        Ok(())
+    }
+    /// E.g. output of `MultiTxBuilder::into_tx_builders`.
+    pub fn add_transactions(&mut self, tx_builders: Vec<TxBuilder<'a>>) -> Result<(), String> {
+        for x in tx_builders {
+            self.add_transaction(x)?;
+        }
+        Ok(())
+    }
+    /// Puts the tip as the last instruction of the last transaction,
+    /// tips already added to the other transactions are dropped.
+    fn into_tipped(mut self, tip: TxTipConfig) -> Result<Vec<TxBuilder<'a>>, String> {
+        let (last, rest) = self
+            .transactions
+            .split_last_mut()
+            .ok_or("Bundle is empty".to_string())?;
+        for x in rest {
+            x.remove_tip_instruction();
+        }
+        last.add_tip_instruction(tip);
+        Ok(self.transactions)
+    }
+    pub fn build_v0_unsigned(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+        tip: TxTipConfig,
+    ) -> Result<Vec<UnsignedTransaction>, String> {
+        self.into_tipped(tip)?
+            .into_iter()
+            .map(|x| x.build_v0_unsigned(recent_blockhash, lookup_table_accounts))
+            .collect()
+    }
+    pub fn build_v0(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+        tip: TxTipConfig,
+    ) -> Result<Vec<VersionedTransaction>, String> {
+        self.into_tipped(tip)?
+            .into_iter()
+            .map(|x| x.build_v0(recent_blockhash, lookup_table_accounts))
+            .collect()
+    }
+    pub async fn build_and_send(
+        self,
+        sender: &dyn BundleSender,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+        tip_accounts: &TipAccounts,
+        tip_lamports: u64,
+    ) -> Result<String, String> {
+        let transactions = self.build_v0(
+            recent_blockhash,
+            lookup_table_accounts,
+            tip_accounts.next_tip_config(tip_lamports),
+        )?;
+        sender.send_bundle(&transactions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::owner::keypair_owner;
+    use solana_sdk::system_program;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct TestBundleSender {
+        sent: Mutex<Vec<VersionedTransaction>>,
+    }
+
+    #[async_trait]
+    impl BundleSender for TestBundleSender {
+        async fn send_bundle(
+            &self,
+            transactions: &[VersionedTransaction],
+        ) -> Result<String, String> {
+            self.sent.lock().unwrap().extend_from_slice(transactions);
+            Ok("bundle".to_string())
+        }
+    }
+
+    /// Number of system program instructions touching `tip_account`.
+    fn count_tips(tx: &VersionedTransaction, tip_account: &Pubkey) -> usize {
+        let keys = tx.message.static_account_keys();
+        tx.message
+            .instructions()
+            .iter()
+            .filter(|x| {
+                keys[x.program_id_index as usize] == system_program::ID
+                    && x.accounts.iter().any(|i| &keys[*i as usize] == tip_account)
+            })
+            .count()
+    }
+
+    #[tokio::test]
+    async fn tips_only_the_last_transaction() {
+        let owner = keypair_owner();
+        let tip_account = Pubkey::new_unique();
+        let tip_accounts = TipAccounts::new(vec![tip_account]).unwrap();
+        let mut bundle = BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS);
+        for i in 0..3 {
+            let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+            tx_builder.add_memo(&format!("tx {}", i), &[]);
+            // E.g. built by `Clmm::swap_tx_builder` with a tip config.
+            tx_builder.add_tip_instruction(TxTipConfig::new(tip_account, 1_000));
+            bundle.add_transaction(tx_builder).unwrap();
+        }
+        let sender = TestBundleSender::default();
+        let bundle_id = bundle
+            .build_and_send(&sender, Hash::new_unique(), &[], &tip_accounts, 5_000)
+            .await
+            .unwrap();
+        assert_eq!(bundle_id, "bundle");
+        let sent = sender.sent.lock().unwrap();
+        let tips: Vec<usize> = sent.iter().map(|x| count_tips(x, &tip_account)).collect();
+        assert_eq!(tips, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn rejects_empty_and_oversized_bundles() {
+        let owner = keypair_owner();
+        let tip = TxTipConfig::new(Pubkey::new_unique(), 1_000);
+        assert!(BundleBuilder::new(MAX_BUNDLE_TRANSACTIONS)
+            .build_v0(Hash::new_unique(), &[], tip)
+            .is_err());
+        let mut bundle = BundleBuilder::new(1);
+        bundle
+            .add_transaction(TxBuilder::new(&owner, owner.pubkey()))
+            .unwrap();
+        assert!(bundle
+            .add_transaction(TxBuilder::new(&owner, owner.pubkey()))
+            .is_err());
+    }
+}
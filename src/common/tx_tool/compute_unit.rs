@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::transaction::VersionedTransaction;
+
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+#[async_trait]
+pub trait TxSimulator: Send + Sync {
+    /// Units consumed by `tx`, signatures are not verified and the blockhash is replaced.
+    async fn simulate_units_consumed(&self, tx: &VersionedTransaction) -> Result<u64, String>;
+}
+
+#[async_trait]
+impl TxSimulator for RpcClient {
+    async fn simulate_units_consumed(&self, tx: &VersionedTransaction) -> Result<u64, String> {
+        let res = self
+            .simulate_transaction_with_config(
+                tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(self.commitment()),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?
+            .value;
+        if let Some(err) = res.err {
+            return Err(format!(
+                "Simulation failed: {}, logs: {:?}",
+                err,
+                res.logs.unwrap_or_default()
+            ));
+        }
+        res.units_consumed
+            .ok_or("Simulation did not return units consumed".to_string())
+    }
+}
+
+pub struct ComputeUnitEstimateConfig {
+    margin_percent: u32,
+    min_units: u32,
+    max_units: u32,
+}
+
+impl ComputeUnitEstimateConfig {
+    pub fn new(margin_percent: u32) -> Self {
+        Self {
+            margin_percent,
+            min_units: 0,
+            max_units: MAX_COMPUTE_UNIT_LIMIT,
+        }
+    }
+    pub fn with_bounds(margin_percent: u32, min_units: u32, max_units: u32) -> Self {
+        let max_units = max_units.min(MAX_COMPUTE_UNIT_LIMIT);
+        Self {
+            margin_percent,
+            min_units: min_units.min(max_units),
+            max_units,
+        }
+    }
+    pub fn max_units(&self) -> u32 {
+        self.max_units
+    }
+    pub fn apply(&self, units_consumed: u64) -> u32 {
+        let units = units_consumed * (100 + self.margin_percent as u64) / 100;
+        units.clamp(self.min_units as u64, self.max_units as u64) as u32
+    }
+}
@@ -0,0 +1,234 @@
+use crate::common::tx_tool::tx_type::InstructionType;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct InstructionDescription {
+    pub instruction_type: Option<InstructionType>,
+    pub program_id: Pubkey,
+    pub accounts: Vec<(&'static str, Pubkey)>,
+    pub amounts: Vec<(&'static str, String)>,
+}
+
+impl fmt::Display for InstructionDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.instruction_type {
+            None => write!(f, "Unknown({})", self.program_id)?,
+            Some(x) => write!(f, "{:?}", x)?,
+        }
+        for (name, value) in &self.accounts {
+            write!(f, " {}={}", name, value)?;
+        }
+        for (name, value) in &self.amounts {
+            write!(f, " {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+fn read_u128(data: &[u8], offset: usize) -> Option<u128> {
+    Some(u128::from_le_bytes(data.get(offset..offset + 16)?.try_into().ok()?))
+}
+
+fn pick_accounts(
+    instruction: &Instruction,
+    names: &[(&'static str, usize)],
+) -> Vec<(&'static str, Pubkey)> {
+    names
+        .iter()
+        .filter_map(|(name, i)| instruction.accounts.get(*i).map(|x| (*name, x.pubkey)))
+        .collect()
+}
+
+fn pick_amounts(values: Vec<(&'static str, Option<String>)>) -> Vec<(&'static str, String)> {
+    values
+        .into_iter()
+        .filter_map(|(name, x)| x.map(|x| (name, x)))
+        .collect()
+}
+
+pub fn describe_instruction(
+    instruction: &Instruction,
+    instruction_type: Option<InstructionType>,
+) -> InstructionDescription {
+    let data = instruction.data.as_slice();
+    let (accounts, amounts) = match &instruction_type {
+        Some(InstructionType::ClmmSwapBaseIn) => (
+            pick_accounts(
+                instruction,
+                &[
+                    ("payer", 0),
+                    ("pool", 2),
+                    ("input_token_account", 3),
+                    ("output_token_account", 4),
+                    ("input_vault", 5),
+                    ("output_vault", 6),
+                    ("input_mint", 11),
+                    ("output_mint", 12),
+                ],
+            ),
+            pick_amounts(vec![
+                ("amount_in", read_u64(data, 8).map(|x| x.to_string())),
+                ("amount_out_min", read_u64(data, 16).map(|x| x.to_string())),
+                ("sqrt_price_limit_x64", read_u128(data, 24).map(|x| x.to_string())),
+            ]),
+        ),
+        Some(InstructionType::CreateAccount) => (
+            pick_accounts(instruction, &[("payer", 0), ("account", 1)]),
+            pick_amounts(vec![("lamports", read_u64(data, 4).map(|x| x.to_string()))]),
+        ),
+        Some(InstructionType::CreateATA) => (
+            pick_accounts(
+                instruction,
+                &[("payer", 0), ("account", 1), ("owner", 2), ("mint", 3)],
+            ),
+            vec![],
+        ),
+        Some(InstructionType::InitAccount) => (
+            pick_accounts(instruction, &[("account", 0), ("mint", 1)]),
+            vec![],
+        ),
+        Some(InstructionType::CloseAccount) => (
+            pick_accounts(
+                instruction,
+                &[("account", 0), ("destination", 1), ("authority", 2)],
+            ),
+            vec![],
+        ),
+        Some(InstructionType::SetComputeUnitPrice) => (
+            vec![],
+            pick_amounts(vec![(
+                "micro_lamports",
+                read_u64(data, 1).map(|x| x.to_string()),
+            )]),
+        ),
+        Some(InstructionType::SetComputeUnitLimit) => (
+            vec![],
+            pick_amounts(vec![("units", read_u32(data, 1).map(|x| x.to_string()))]),
+        ),
+        Some(InstructionType::TransferTip) => (
+            pick_accounts(instruction, &[("from", 0), ("tip_account", 1)]),
+            pick_amounts(vec![("lamports", read_u64(data, 4).map(|x| x.to_string()))]),
+        ),
+        Some(InstructionType::AdvanceNonce) => (
+            pick_accounts(instruction, &[("nonce_account", 0), ("nonce_authority", 2)]),
+            vec![],
+        ),
+        Some(InstructionType::Memo) => (
+            instruction
+                .accounts
+                .iter()
+                .map(|x| ("signer", x.pubkey))
+                .collect(),
+            vec![("memo", String::from_utf8_lossy(data).to_string())],
+        ),
+        None => (vec![], vec![]),
+    };
+    InstructionDescription {
+        instruction_type,
+        program_id: instruction.program_id,
+        accounts,
+        amounts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::tpe::{Base, ClmmKeys, MintProgram, Vault};
+    use crate::common::owner::keypair_owner;
+    use crate::common::tx_tool::{ComputeBudgetConfig, TxBuilder, TxTipConfig};
+    use crate::raydium::account::instrument::AccountInstrument;
+    use crate::raydium::clmm::instrument::{ClmmInstrument, OwnerInfo};
+    use crate::raydium::clmm::tpe::ComputeClmmPoolInfo;
+    use crate::raydium::clmm::utils::constants::MIN_SQRT_PRICE_X64_ADD_ONE;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn describes_swap_transaction() {
+        let owner = keypair_owner();
+        let wallet = owner.pubkey();
+        let (mint0, mint1) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool_info = ComputeClmmPoolInfo::test_pool(mint0, mint1);
+        let pool_keys = ClmmKeys {
+            base: Base {
+                lookup_table_account: None,
+                vault: Vault {
+                    a: Pubkey::new_unique(),
+                    b: Pubkey::new_unique(),
+                },
+                mint_program: MintProgram {
+                    a: spl_token::ID,
+                    b: spl_token::ID,
+                },
+            },
+        };
+        let tip_account = Pubkey::new_unique();
+        let token_account_a = Pubkey::new_unique();
+        let (ata, ata_instructions) =
+            AccountInstrument::make_create_ata_instructions(&wallet, &wallet, &mint1, &spl_token::ID);
+
+        let mut tx_builder = TxBuilder::new(&owner, wallet);
+        tx_builder.add_instruction(ata_instructions);
+        tx_builder.add_instruction(ClmmInstrument::make_swap_base_in_instructions(
+            &pool_info,
+            &pool_keys,
+            &Pubkey::new_unique(),
+            &OwnerInfo {
+                wallet,
+                token_account_a,
+                token_account_b: ata,
+            },
+            &mint0,
+            rug::Integer::from(1_000_000),
+            rug::Integer::from(990_000),
+            MIN_SQRT_PRICE_X64_ADD_ONE,
+            vec![],
+        ));
+        tx_builder.add_custom_compute_budget(ComputeBudgetConfig::new(200_000, 1_000));
+        tx_builder.add_tip_instruction(TxTipConfig::new(tip_account, 10_000));
+
+        let res: Vec<String> = tx_builder
+            .describe()
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(
+            res,
+            vec![
+                "SetComputeUnitPrice micro_lamports=1000".to_string(),
+                "SetComputeUnitLimit units=200000".to_string(),
+                format!(
+                    "CreateATA payer={} account={} owner={} mint={}",
+                    wallet, ata, wallet, mint1
+                ),
+                format!(
+                    "ClmmSwapBaseIn payer={} pool={} input_token_account={} output_token_account={} \
+                     input_vault={} output_vault={} input_mint={} output_mint={} \
+                     amount_in=1000000 amount_out_min=990000 sqrt_price_limit_x64={}",
+                    wallet,
+                    pool_info.id,
+                    token_account_a,
+                    ata,
+                    pool_keys.base.vault.a,
+                    pool_keys.base.vault.b,
+                    mint0,
+                    mint1,
+                    MIN_SQRT_PRICE_X64_ADD_ONE
+                ),
+                format!(
+                    "TransferTip from={} tip_account={} lamports=10000",
+                    wallet, tip_account
+                ),
+            ]
+        );
+    }
+}
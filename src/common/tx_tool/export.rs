@@ -0,0 +1,207 @@
+use crate::common::tx_tool::tx_type::InstructionType;
+use crate::common::tx_tool::unsigned_tx::UnsignedTransaction;
+use crate::common::tx_tool::TxBuilder;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::str::FromStr;
+
+impl UnsignedTransaction {
+    /// Wire format, missing signatures are zeroed.
+    pub fn to_wire(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(&self.to_partial_transaction()).map_err(|e| e.to_string())
+    }
+    pub fn from_wire(data: &[u8]) -> Result<Self, String> {
+        let tx: VersionedTransaction = bincode::deserialize(data)
+            .map_err(|e| format!("Failed to decode transaction: {}", e))?;
+        Self::from_transaction(tx)
+    }
+    pub fn to_base64(&self) -> Result<String, String> {
+        Ok(STANDARD.encode(self.to_wire()?))
+    }
+    pub fn from_base64(data: &str) -> Result<Self, String> {
+        Self::from_wire(&STANDARD.decode(data).map_err(|e| e.to_string())?)
+    }
+    pub fn to_base58(&self) -> Result<String, String> {
+        Ok(bs58::encode(self.to_wire()?).into_string())
+    }
+    pub fn from_base58(data: &str) -> Result<Self, String> {
+        Self::from_wire(&bs58::decode(data).into_vec().map_err(|e| e.to_string())?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LookupTableEnvelope {
+    pub address: String,
+    pub addresses: Vec<String>,
+}
+
+/// Everything an offline signer needs to review and sign a transaction.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TxEnvelope {
+    /// Base64 of the serialized message, the bytes to be signed.
+    pub message: String,
+    pub signers: Vec<String>,
+    /// Base58 signatures aligned with `signers`, `None` while missing.
+    pub signatures: Vec<Option<String>>,
+    pub lookup_tables: Vec<LookupTableEnvelope>,
+    pub instruction_types: Vec<InstructionType>,
+}
+
+impl TxEnvelope {
+    pub fn new(
+        tx: &UnsignedTransaction,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+        instruction_types: Vec<InstructionType>,
+    ) -> Self {
+        let used_tables: Vec<Pubkey> = match tx.message() {
+            VersionedMessage::Legacy(_) => vec![],
+            VersionedMessage::V0(x) => x
+                .address_table_lookups
+                .iter()
+                .map(|x| x.account_key)
+                .collect(),
+        };
+        Self {
+            message: STANDARD.encode(tx.message_data()),
+            signers: tx.signers().iter().map(|x| x.to_string()).collect(),
+            signatures: tx
+                .signatures()
+                .iter()
+                .map(|x| {
+                    if x == &Signature::default() {
+                        None
+                    } else {
+                        Some(x.to_string())
+                    }
+                })
+                .collect(),
+            lookup_tables: lookup_table_accounts
+                .iter()
+                .filter(|x| used_tables.contains(&x.key))
+                .map(|x| LookupTableEnvelope {
+                    address: x.key.to_string(),
+                    addresses: x.addresses.iter().map(|x| x.to_string()).collect(),
+                })
+                .collect(),
+            instruction_types,
+        }
+    }
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+    pub fn from_json(data: &str) -> Result<Self, String> {
+        serde_json::from_str(data).map_err(|e| e.to_string())
+    }
+    pub fn lookup_table_accounts(&self) -> Result<Vec<AddressLookupTableAccount>, String> {
+        self.lookup_tables
+            .iter()
+            .map(|x| {
+                Ok(AddressLookupTableAccount {
+                    key: parse_pubkey(&x.address)?,
+                    addresses: x
+                        .addresses
+                        .iter()
+                        .map(|x| parse_pubkey(x))
+                        .collect::<Result<Vec<Pubkey>, String>>()?,
+                })
+            })
+            .collect()
+    }
+    /// Restores the transaction and checks the envelope signers against the message.
+    pub fn to_unsigned_transaction(&self) -> Result<UnsignedTransaction, String> {
+        let data = STANDARD.decode(&self.message).map_err(|e| e.to_string())?;
+        let message: VersionedMessage = bincode::deserialize(&data)
+            .map_err(|e| format!("Failed to decode message: {}", e))?;
+        let mut tx = UnsignedTransaction::new(message);
+        let signers = self
+            .signers
+            .iter()
+            .map(|x| parse_pubkey(x))
+            .collect::<Result<Vec<Pubkey>, String>>()?;
+        if &signers != tx.signers() {
+            return Err("Envelope signers do not match the message".to_string());
+        }
+        if self.signatures.len() != signers.len() {
+            return Err("Envelope signatures do not match signers".to_string());
+        }
+        for (signer, signature) in signers.iter().zip(self.signatures.iter()) {
+            if let Some(x) = signature {
+                let signature = Signature::from_str(x).map_err(|e| e.to_string())?;
+                tx.add_signature(signer, signature)?;
+            }
+        }
+        Ok(tx)
+    }
+}
+
+fn parse_pubkey(x: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(x).map_err(|e| format!("Invalid pubkey {}: {}", x, e))
+}
+
+impl TxBuilder<'_> {
+    pub fn export_v0(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<TxEnvelope, String> {
+        let instruction_types = self.all_instruction_types();
+        let tx = self.build_v0_unsigned(recent_blockhash, lookup_table_accounts)?;
+        Ok(TxEnvelope::new(&tx, lookup_table_accounts, instruction_types))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::owner::offline_owner;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+
+    #[test]
+    fn envelope_round_trip_and_offline_signing() {
+        let (owner, offline) = offline_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_memo("offline", &[owner.pubkey()]);
+        let json = tx_builder
+            .export_v0(Hash::new_unique(), &[])
+            .unwrap()
+            .to_json()
+            .unwrap();
+
+        let envelope = TxEnvelope::from_json(&json).unwrap();
+        assert_eq!(envelope.signers, vec![offline.pubkey().to_string()]);
+        assert_eq!(envelope.signatures, vec![None]);
+        assert_eq!(envelope.instruction_types, vec![InstructionType::Memo]);
+
+        let mut tx = envelope.to_unsigned_transaction().unwrap();
+        assert_eq!(tx.missing_signers(), vec![offline.pubkey()]);
+        tx.sign(&offline).unwrap();
+        let restored = UnsignedTransaction::from_base64(&tx.to_base64().unwrap()).unwrap();
+        assert!(restored.is_fully_signed());
+        let tx = restored.into_transaction().unwrap();
+        assert!(tx.verify_with_results().into_iter().all(|x| x));
+    }
+
+    #[test]
+    fn envelope_rejects_foreign_signatures_and_signers() {
+        let (owner, _) = offline_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_memo("offline", &[owner.pubkey()]);
+        let mut envelope = tx_builder.export_v0(Hash::new_unique(), &[]).unwrap();
+
+        let message = STANDARD.decode(&envelope.message).unwrap();
+        envelope.signatures = vec![Some(Keypair::new().sign_message(&message).to_string())];
+        assert!(envelope.to_unsigned_transaction().is_err());
+
+        envelope.signatures = vec![None];
+        envelope.signers = vec![Pubkey::new_unique().to_string()];
+        assert!(envelope.to_unsigned_transaction().is_err());
+    }
+}
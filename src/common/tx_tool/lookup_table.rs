@@ -0,0 +1,193 @@
+use crate::common::util::MAX_MULTIPLE_ACCOUNTS;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Default)]
+pub struct LookupTableCache {
+    tables: HashMap<Pubkey, AddressLookupTableAccount>,
+}
+
+impl LookupTableCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn insert(&mut self, table: AddressLookupTableAccount) {
+        self.tables.insert(table.key, table);
+    }
+    pub fn get(&self, address: &Pubkey) -> Option<&AddressLookupTableAccount> {
+        self.tables.get(address)
+    }
+    pub fn remove(&mut self, address: &Pubkey) -> Option<AddressLookupTableAccount> {
+        self.tables.remove(address)
+    }
+    /// Returns cached tables for `addresses`, fetching the missing ones.
+    pub async fn get_or_fetch(
+        &mut self,
+        client: &RpcClient,
+        addresses: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>, String> {
+        let addresses = dedup_addresses(addresses);
+        let missing: Vec<Pubkey> = addresses
+            .iter()
+            .filter(|x| !self.tables.contains_key(x))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            for table in fetch_lookup_table_accounts(client, &missing, commitment).await? {
+                self.insert(table);
+            }
+        }
+        addresses
+            .iter()
+            .map(|x| {
+                self.tables
+                    .get(x)
+                    .cloned()
+                    .ok_or(format!("Lookup table {} was not found", x))
+            })
+            .collect()
+    }
+}
+
+pub fn dedup_addresses(addresses: &[Pubkey]) -> Vec<Pubkey> {
+    let mut res: Vec<Pubkey> = Vec::with_capacity(addresses.len());
+    for x in addresses {
+        if x != &Pubkey::default() && !res.contains(x) {
+            res.push(*x);
+        }
+    }
+    res
+}
+
+pub fn parse_lookup_table_addresses(addresses: &[String]) -> Result<Vec<Pubkey>, String> {
+    let parsed = addresses
+        .iter()
+        .map(|x| Pubkey::from_str(x).map_err(|e| format!("Invalid lookup table {}: {}", x, e)))
+        .collect::<Result<Vec<Pubkey>, String>>()?;
+    Ok(dedup_addresses(&parsed))
+}
+
+pub fn decode_lookup_table_account(
+    address: &Pubkey,
+    data: &[u8],
+) -> Result<AddressLookupTableAccount, String> {
+    let table = AddressLookupTable::deserialize(data)
+        .map_err(|e| format!("Failed to decode lookup table {}: {}", address, e))?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+pub async fn fetch_lookup_table_accounts(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+    commitment: CommitmentConfig,
+) -> Result<Vec<AddressLookupTableAccount>, String> {
+    let addresses = dedup_addresses(addresses);
+    let mut res = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client
+            .get_multiple_accounts_with_commitment(chunk, commitment)
+            .await
+            .map_err(|e| e.to_string())?
+            .value;
+        for (address, account) in chunk.iter().zip(accounts.into_iter()) {
+            let account = account.ok_or(format!("Lookup table {} was not found", address))?;
+            res.push(decode_lookup_table_account(address, &account.data)?);
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::owner::keypair_owner;
+    use crate::common::tx_tool::{compile_v0_message, transaction_size, TxBuilder};
+    use crate::common::tx_tool::tx_type::InstructionType;
+    use crate::raydium::tpe::MakeInstructionsResult;
+    use solana_sdk::address_lookup_table::state::LookupTableMeta;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use std::borrow::Cow;
+
+    #[test]
+    fn dedup_addresses_keeps_order_and_drops_default() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            dedup_addresses(&[b, Pubkey::default(), a, b, a]),
+            vec![b, a]
+        );
+    }
+
+    #[test]
+    fn decodes_lookup_table_account() {
+        let address = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let data = AddressLookupTable {
+            meta: LookupTableMeta {
+                deactivation_slot: u64::MAX,
+                last_extended_slot: 1,
+                last_extended_slot_start_index: 0,
+                authority: Some(Pubkey::new_unique()),
+                _padding: 0,
+            },
+            addresses: Cow::Owned(addresses.clone()),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        let table = decode_lookup_table_account(&address, &data).unwrap();
+        assert_eq!(table.key, address);
+        assert_eq!(table.addresses, addresses);
+        assert!(decode_lookup_table_account(&address, &data[..10]).is_err());
+    }
+
+    #[test]
+    fn lookup_table_shrinks_message_until_it_fits() {
+        let owner = keypair_owner();
+        let program_id = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        for chunk in accounts.chunks(10) {
+            tx_builder.add_instruction(MakeInstructionsResult::new(
+                vec![Instruction::new_with_bytes(
+                    program_id,
+                    &[],
+                    chunk.iter().map(|x| AccountMeta::new(*x, false)).collect(),
+                )],
+                vec![InstructionType::Memo],
+            ));
+        }
+        let err = tx_builder.compile_v0(Hash::new_unique(), &[]).unwrap_err();
+        assert!(err.contains("too large"), "{}", err);
+        let without_table = transaction_size(
+            &compile_v0_message(
+                &owner.pubkey(),
+                &tx_builder.all_instructions(),
+                &[],
+                Hash::new_unique(),
+            )
+            .unwrap(),
+        );
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts,
+        };
+        let message = tx_builder
+            .compile_v0(Hash::new_unique(), &[table.clone(), table])
+            .unwrap();
+        assert_eq!(message.static_account_keys().len(), 2);
+        assert_eq!(message.address_table_lookups().unwrap().len(), 1);
+        let with_table = transaction_size(&message);
+        assert!(with_table < without_table);
+        assert!(with_table <= solana_sdk::packet::PACKET_DATA_SIZE);
+    }
+}
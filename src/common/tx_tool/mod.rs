@@ -1,27 +1,50 @@
+pub mod bundle;
+pub mod compute_unit;
+pub mod describe;
+pub mod export;
+pub mod lookup_table;
+pub mod multi_tx;
+pub mod nonce;
+pub mod priority_fee;
+pub mod sender;
 pub mod tx_type;
+pub mod unsigned_tx;
 
+use crate::common::memo::make_memo_instruction;
 use crate::common::owner::Owner;
+use crate::common::tx_tool::compute_unit::{ComputeUnitEstimateConfig, TxSimulator};
+use crate::common::tx_tool::describe::{describe_instruction, InstructionDescription};
+use crate::common::tx_tool::lookup_table::{dedup_addresses, parse_lookup_table_addresses};
+use crate::common::tx_tool::nonce::DurableNonceConfig;
 use crate::common::tx_tool::tx_type::InstructionType;
+use crate::common::tx_tool::unsigned_tx::UnsignedTransaction;
 use crate::raydium::tpe::MakeInstructionsResult;
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::hash::Hash;
 use solana_sdk::instruction::Instruction;
-use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::message::{v0, Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
 use solana_sdk::system_instruction;
 
 pub struct TxBuilder<'a> {
     owner: &'a Owner,
     fee_payer: Pubkey,
+    fee_payer_signer: Option<&'a Owner>,
     instructions: Vec<Instruction>,
-    // end_instructions: ,
-    // signers: Vec<>,
+    end_instructions: Vec<Instruction>,
+    signers: Vec<Keypair>,
     instruction_types: Vec<InstructionType>,
-    // end_instruction_types: ,
+    end_instruction_types: Vec<InstructionType>,
     lookup_table_address: Vec<String>,
+    durable_nonce: Option<DurableNonceConfig>,
+    tip: Option<TxTipConfig>,
 }
+#[derive(Clone, Copy)]
 pub struct ComputeBudgetConfig {
     units: u32,
     micro_lamports: u64,
@@ -30,7 +53,14 @@ impl ComputeBudgetConfig {
     pub fn new(units: u32, micro_lamports: u64) -> Self {
         Self { units, micro_lamports }
     }
+    pub fn instructions(&self) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_price(self.micro_lamports),
+            ComputeBudgetInstruction::set_compute_unit_limit(self.units),
+        ]
+    }
 }
+#[derive(Clone, Copy)]
 pub struct TxTipConfig {
     tip_account: Pubkey,
     lamports: u64,
@@ -39,70 +69,543 @@ impl TxTipConfig {
     pub fn new(tip_account: Pubkey, lamports: u64) -> Self {
         Self { tip_account, lamports }
     }
+    pub fn instruction(&self, from: &Pubkey) -> Instruction {
+        system_instruction::transfer(from, &self.tip_account, self.lamports)
+    }
 }
 impl<'a> TxBuilder<'a> {
     pub fn new(owner: &'a Owner, fee_payer: Pubkey) -> Self {
         Self {
             owner,
             fee_payer,
+            fee_payer_signer: None,
             instructions: vec![],
+            end_instructions: vec![],
+            signers: vec![],
             instruction_types: vec![],
+            end_instruction_types: vec![],
             lookup_table_address: vec![],
+            durable_nonce: None,
+            tip: None,
+        }
+    }
+    /// Fee payer distinct from the owner which signs alongside it.
+    pub fn new_with_fee_payer_signer(owner: &'a Owner, fee_payer: &'a Owner) -> Self {
+        let mut res = Self::new(owner, fee_payer.pubkey());
+        if fee_payer.pubkey() != owner.pubkey() {
+            res.fee_payer_signer = Some(fee_payer);
+        }
+        res
+    }
+    pub fn fee_payer(&self) -> Pubkey {
+        self.fee_payer
+    }
+    fn signing_owners(&self) -> Vec<&'a Owner> {
+        let mut res = vec![self.owner];
+        if let Some(x) = self.fee_payer_signer {
+            res.push(x);
         }
+        res
     }
+    /// Signatures of keypairs created by instruction builders (new mints, temporary accounts)
+    /// are attached right away, only the owner and the fee payer remain.
+    fn sign_with_instruction_signers(&self, tx: &mut UnsignedTransaction) -> Result<(), String> {
+        for x in &self.signers {
+            if tx.signers().contains(&x.pubkey()) {
+                tx.sign(x)?;
+            }
+        }
+        Ok(())
+    }
+    /// The tip is always the last instruction, a second call replaces the first tip.
     pub fn add_tip_instruction(&mut self, cfg: TxTipConfig) {
-        //@TODO endinstructions
-        self.instructions.push(system_instruction::transfer(
-            &self.fee_payer,
-            &cfg.tip_account,
-            cfg.lamports,
-        ));
-        self.instruction_types.push(InstructionType::TransferTip);
+        self.tip = Some(cfg);
+    }
+    pub fn remove_tip_instruction(&mut self) -> Option<TxTipConfig> {
+        self.tip.take()
     }
+    /// Replaces the existing price and limit instructions or prepends new ones.
     pub fn add_custom_compute_budget(&mut self, cfg: ComputeBudgetConfig) {
-        self.instructions.splice(
-            0..0,
-            vec![
-                ComputeBudgetInstruction::set_compute_unit_price(cfg.micro_lamports),
-                ComputeBudgetInstruction::set_compute_unit_limit(cfg.units),
-            ],
+        self.set_compute_unit_limit(cfg.units);
+        self.set_compute_budget_instruction(
+            ComputeBudgetInstruction::set_compute_unit_price(cfg.micro_lamports),
+            InstructionType::SetComputeUnitPrice,
         );
-        self.instruction_types.splice(
-            0..0,
-            vec![
-                InstructionType::SetComputeUnitPrice,
-                InstructionType::SetComputeUnitLimit,
-            ],
+    }
+    /// Replaces the existing limit instruction or prepends a new one.
+    pub fn set_compute_unit_limit(&mut self, units: u32) {
+        self.set_compute_budget_instruction(
+            ComputeBudgetInstruction::set_compute_unit_limit(units),
+            InstructionType::SetComputeUnitLimit,
         );
     }
+    fn set_compute_budget_instruction(
+        &mut self,
+        instruction: Instruction,
+        instruction_type: InstructionType,
+    ) {
+        match self
+            .instruction_types
+            .iter()
+            .position(|x| *x == instruction_type)
+        {
+            Some(i) => self.instructions[i] = instruction,
+            None => {
+                self.instructions.insert(0, instruction);
+                self.instruction_types.insert(0, instruction_type);
+            }
+        }
+    }
+    /// Simulates the transaction with the maximum limit and sets the limit
+    /// to the consumed units plus the configured margin.
+    pub async fn estimate_compute_unit_limit(
+        &mut self,
+        simulator: &dyn TxSimulator,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+        cfg: &ComputeUnitEstimateConfig,
+    ) -> Result<u32, String> {
+        // Restored on failure, so the builder never keeps the maximum limit.
+        let instructions = self.instructions.clone();
+        let instruction_types = self.instruction_types.clone();
+        self.set_compute_unit_limit(cfg.max_units());
+        let units_consumed = match self
+            .simulate_units_consumed(simulator, lookup_table_accounts)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                self.instructions = instructions;
+                self.instruction_types = instruction_types;
+                return Err(e);
+            }
+        };
+        let units = cfg.apply(units_consumed);
+        self.set_compute_unit_limit(units);
+        Ok(units)
+    }
+    async fn simulate_units_consumed(
+        &self,
+        simulator: &dyn TxSimulator,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<u64, String> {
+        let message = compile_v0_message(
+            &self.fee_payer,
+            &self.all_instructions(),
+            lookup_table_accounts,
+            Hash::default(),
+        )?;
+        let tx = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        };
+        simulator.simulate_units_consumed(&tx).await
+    }
+    /// Memo at the current position, add it before the instructions it has to precede,
+    /// e.g. transfers into accounts requiring incoming memos.
+    pub fn add_memo(&mut self, memo: &str, signers: &[Pubkey]) {
+        self.instructions.push(make_memo_instruction(memo, signers));
+        self.instruction_types.push(InstructionType::Memo);
+    }
     pub fn add_instruction(&mut self, mut instruction: MakeInstructionsResult) {
-        //@TODO endInstructions, signers, endInstructionTypes
         self.instructions.append(&mut instruction.instructions);
         self.instruction_types
             .append(&mut instruction.instruction_types);
+        self.end_instructions
+            .append(&mut instruction.end_instructions);
+        self.end_instruction_types
+            .append(&mut instruction.end_instruction_types);
+        self.signers.append(&mut instruction.signers);
         let def_pub = Pubkey::default().to_string();
-        self.lookup_table_address.extend(
-            &mut instruction
-                .lookup_table_address
-                .into_iter()
-                .filter(|x| x != &def_pub),
+        for x in instruction.lookup_table_address {
+            if x != def_pub && !self.lookup_table_address.contains(&x) {
+                self.lookup_table_address.push(x);
+            }
+        }
+    }
+    /// Instructions in execution order, end instructions last.
+    pub fn all_instructions(&self) -> Vec<Instruction> {
+        let mut res = match &self.durable_nonce {
+            None => vec![],
+            Some(x) => vec![x.advance_instruction()],
+        };
+        res.extend(self.instructions.iter().cloned());
+        res.extend(self.end_instructions.iter().cloned());
+        if let Some(x) = &self.tip {
+            res.push(x.instruction(&self.fee_payer));
+        }
+        res
+    }
+    /// Types aligned with `all_instructions`.
+    pub fn all_instruction_types(&self) -> Vec<InstructionType> {
+        let mut res = match &self.durable_nonce {
+            None => vec![],
+            Some(_) => vec![InstructionType::AdvanceNonce],
+        };
+        res.extend(self.instruction_types.iter().cloned());
+        res.extend(self.end_instruction_types.iter().cloned());
+        if self.tip.is_some() {
+            res.push(InstructionType::TransferTip);
+        }
+        res
+    }
+    /// What is about to be signed, one entry per instruction.
+    pub fn describe(&self) -> Vec<InstructionDescription> {
+        let instructions = self.all_instructions();
+        let mut instruction_types = self.all_instruction_types();
+        if instruction_types.len() != instructions.len() {
+            instruction_types.clear();
+        }
+        instructions
+            .iter()
+            .enumerate()
+            .map(|(i, x)| describe_instruction(x, instruction_types.get(i).cloned()))
+            .collect()
+    }
+    /// Builds against a durable nonce: the advance instruction goes first and
+    /// the nonce value (`DurableNonceConfig::fetch_blockhash`) must be passed as the blockhash.
+    pub fn set_durable_nonce(&mut self, cfg: DurableNonceConfig) {
+        self.durable_nonce = Some(cfg);
+    }
+    pub fn durable_nonce(&self) -> Option<&DurableNonceConfig> {
+        self.durable_nonce.as_ref()
+    }
+    /// Writable accounts except the fee payer, the ones priority fees compete for.
+    pub fn writable_accounts(&self) -> Vec<Pubkey> {
+        let mut res: Vec<Pubkey> = vec![];
+        for instruction in self.all_instructions() {
+            for x in instruction.accounts {
+                if x.is_writable && x.pubkey != self.fee_payer && !res.contains(&x.pubkey) {
+                    res.push(x.pubkey);
+                }
+            }
+        }
+        res
+    }
+    /// Lookup tables requested by the added instructions, resolve them with
+    /// `lookup_table::LookupTableCache` or `lookup_table::fetch_lookup_table_accounts`.
+    pub fn lookup_table_addresses(&self) -> Result<Vec<Pubkey>, String> {
+        parse_lookup_table_addresses(&self.lookup_table_address)
+    }
+    pub fn compile_v0(
+        &self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<VersionedMessage, String> {
+        let message = compile_v0_message(
+            &self.fee_payer,
+            &self.all_instructions(),
+            lookup_table_accounts,
+            recent_blockhash,
+        )?;
+        let size = transaction_size(&message);
+        if size > PACKET_DATA_SIZE {
+            return Err(format!(
+                "Transaction is too large ({} > {} bytes), use MultiTxBuilder to split it",
+                size, PACKET_DATA_SIZE
+            ));
+        }
+        Ok(message)
+    }
+    /// Builds the message without signing, for owners without a local keypair.
+    /// Attach signatures with `UnsignedTransaction::add_signature` or `sign`.
+    pub fn build_v0_unsigned(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<UnsignedTransaction, String> {
+        let mut tx = UnsignedTransaction::new(
+            self.compile_v0(recent_blockhash, lookup_table_accounts)?,
+        );
+        self.sign_with_instruction_signers(&mut tx)?;
+        Ok(tx)
+    }
+    pub fn build_v0(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<VersionedTransaction, String> {
+        let owners = self.signing_owners();
+        let mut tx = self.build_v0_unsigned(recent_blockhash, lookup_table_accounts)?;
+        sign_with_local_signers(&mut tx, &owners)?;
+        tx.into_transaction()
+    }
+    pub fn compile_legacy(&self, recent_blockhash: Hash) -> Result<VersionedMessage, String> {
+        let message = VersionedMessage::Legacy(Message::new_with_blockhash(
+            &self.all_instructions(),
+            Some(&self.fee_payer),
+            &recent_blockhash,
+        ));
+        let size = transaction_size(&message);
+        if size > PACKET_DATA_SIZE {
+            return Err(format!(
+                "Legacy transaction is too large ({} > {} bytes), lookup tables are required, use build_v0",
+                size, PACKET_DATA_SIZE
+            ));
+        }
+        Ok(message)
+    }
+    pub fn build_legacy_unsigned(self, recent_blockhash: Hash) -> Result<UnsignedTransaction, String> {
+        let mut tx = UnsignedTransaction::new(self.compile_legacy(recent_blockhash)?);
+        self.sign_with_instruction_signers(&mut tx)?;
+        Ok(tx)
+    }
+    pub fn build_legacy(self, recent_blockhash: Hash) -> Result<Transaction, String> {
+        let owners = self.signing_owners();
+        let mut tx = self.build_legacy_unsigned(recent_blockhash)?;
+        sign_with_local_signers(&mut tx, &owners)?;
+        tx.into_transaction()?
+            .into_legacy_transaction()
+            .ok_or("Failed to convert into legacy transaction".to_string())
+    }
+    /// Signs with the owner, the fee payer and `additional_signers`, remote signers included.
+    pub async fn build_v0_signed(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+        additional_signers: &[&Owner],
+    ) -> Result<VersionedTransaction, String> {
+        let mut owners = self.signing_owners();
+        owners.extend_from_slice(additional_signers);
+        let mut tx = self.build_v0_unsigned(recent_blockhash, lookup_table_accounts)?;
+        tx.sign_with_owners(&owners).await?;
+        tx.into_transaction()
+    }
+}
+
+fn sign_with_local_signers(tx: &mut UnsignedTransaction, owners: &[&Owner]) -> Result<(), String> {
+    for owner in owners {
+        if !tx.signers().contains(&owner.pubkey()) {
+            continue;
+        }
+        let signer = owner.signer().ok_or(format!(
+            "Signer {} has no local signer, use build_v0_signed or an unsigned build",
+            owner.pubkey()
+        ))?;
+        tx.sign(signer)?;
+    }
+    Ok(())
+}
+
+/// Size of the signed transaction on the wire.
+pub fn transaction_size(message: &VersionedMessage) -> usize {
+    let num_signatures = message.header().num_required_signatures as usize;
+    // short_vec length prefix, one byte below 128 signatures
+    1 + num_signatures * 64 + message.serialize().len()
+}
+
+pub(crate) fn compile_v0_message(
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_table_accounts: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage, String> {
+    let lookup_table = dedup_lookup_table_accounts(lookup_table_accounts);
+    Ok(VersionedMessage::V0(
+        v0::Message::try_compile(fee_payer, instructions, &lookup_table, recent_blockhash)
+            .map_err(|e| e.to_string())?,
+    ))
+}
+
+fn dedup_lookup_table_accounts(
+    lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Vec<AddressLookupTableAccount> {
+    let keys: Vec<Pubkey> = lookup_table_accounts.iter().map(|x| x.key).collect();
+    dedup_addresses(&keys)
+        .into_iter()
+        .filter_map(|key| lookup_table_accounts.iter().find(|x| x.key == key).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::owner::{keypair_owner, OwnerKind, RemoteSigner};
+    use async_trait::async_trait;
+    use solana_sdk::instruction::AccountMeta;
+    use std::sync::Mutex;
+
+    struct TestRemoteSigner(Keypair);
+
+    #[async_trait]
+    impl RemoteSigner for TestRemoteSigner {
+        fn pubkey(&self) -> Pubkey {
+            self.0.pubkey()
+        }
+        async fn sign_message(&self, message: &[u8]) -> Result<Signature, String> {
+            Ok(self.0.sign_message(message))
+        }
+    }
+
+    fn remote_owner() -> Owner {
+        Owner::new(OwnerKind::RemoteSigner(Box::new(TestRemoteSigner(
+            Keypair::new(),
+        ))))
+    }
+
+    #[test]
+    fn build_v0_rejects_remote_signer() {
+        let owner = remote_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_memo("swap", &[owner.pubkey()]);
+        assert!(tx_builder.build_v0(Hash::new_unique(), &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn build_v0_signed_with_remote_signer_and_fee_payer() {
+        let owner = remote_owner();
+        let fee_payer = keypair_owner();
+        let mut tx_builder = TxBuilder::new_with_fee_payer_signer(&owner, &fee_payer);
+        tx_builder.add_memo("swap", &[owner.pubkey()]);
+        let tx = tx_builder
+            .build_v0_signed(Hash::new_unique(), &[], &[])
+            .await
+            .unwrap();
+        assert_eq!(tx.message.static_account_keys()[0], fee_payer.pubkey());
+        assert_eq!(tx.signatures.len(), 2);
+        assert!(tx.verify_with_results().into_iter().all(|x| x));
+    }
+
+    /// Records the limit it was simulated with.
+    struct TestSimulator {
+        units_consumed: Result<u64, String>,
+        simulated_limit: Mutex<Option<u32>>,
+    }
+
+    impl TestSimulator {
+        fn new(units_consumed: Result<u64, String>) -> Self {
+            Self {
+                units_consumed,
+                simulated_limit: Mutex::new(None),
+            }
+        }
+    }
+
+    fn compute_unit_limit(instructions: &[Instruction]) -> Option<u32> {
+        instructions
+            .iter()
+            .find(|x| x.program_id == solana_sdk::compute_budget::ID && x.data[0] == 2)
+            .map(|x| u32::from_le_bytes(x.data[1..5].try_into().unwrap()))
+    }
+
+    #[async_trait]
+    impl TxSimulator for TestSimulator {
+        async fn simulate_units_consumed(&self, tx: &VersionedTransaction) -> Result<u64, String> {
+            let message = tx.message.clone();
+            let instructions: Vec<Instruction> = message
+                .instructions()
+                .iter()
+                .map(|x| Instruction {
+                    program_id: message.static_account_keys()[x.program_id_index as usize],
+                    accounts: vec![],
+                    data: x.data.clone(),
+                })
+                .collect();
+            *self.simulated_limit.lock().unwrap() = compute_unit_limit(&instructions);
+            self.units_consumed.clone()
+        }
+    }
+
+    #[test]
+    fn add_custom_compute_budget_replaces_existing_instructions() {
+        let owner = keypair_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_memo("swap", &[]);
+        tx_builder.add_custom_compute_budget(ComputeBudgetConfig::new(200_000, 1));
+        tx_builder.set_compute_unit_limit(250_000);
+        tx_builder.add_custom_compute_budget(ComputeBudgetConfig::new(300_000, 5));
+        assert_eq!(
+            tx_builder.all_instruction_types(),
+            vec![
+                InstructionType::SetComputeUnitPrice,
+                InstructionType::SetComputeUnitLimit,
+                InstructionType::Memo,
+            ]
+        );
+        let instructions = tx_builder.all_instructions();
+        assert_eq!(
+            instructions[..2],
+            ComputeBudgetConfig::new(300_000, 5).instructions()
         );
     }
-    pub fn build_v0(self, recent_blockhash: Hash) -> VersionedTransaction {
-        // @TODO
-        let lookup_table: Vec<AddressLookupTableAccount> = vec![];
-        VersionedTransaction::try_new(
-            VersionedMessage::V0(
-                v0::Message::try_compile(
-                    &self.fee_payer,
-                    &self.instructions,
-                    &lookup_table,
-                    recent_blockhash,
-                )
-                .unwrap(),
-            ),
-            &[self.owner.keypair().unwrap()],
-        )
-        .unwrap()
+
+    #[tokio::test]
+    async fn estimate_compute_unit_limit_applies_margin() {
+        let owner = keypair_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_memo("swap", &[]);
+        let simulator = TestSimulator::new(Ok(100_000));
+        let units = tx_builder
+            .estimate_compute_unit_limit(&simulator, &[], &ComputeUnitEstimateConfig::new(10))
+            .await
+            .unwrap();
+        assert_eq!(units, 110_000);
+        assert_eq!(
+            *simulator.simulated_limit.lock().unwrap(),
+            Some(compute_unit::MAX_COMPUTE_UNIT_LIMIT)
+        );
+        assert_eq!(
+            compute_unit_limit(&tx_builder.all_instructions()),
+            Some(110_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn estimate_compute_unit_limit_restores_limit_on_error() {
+        let owner = keypair_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_custom_compute_budget(ComputeBudgetConfig::new(300_000, 1));
+        tx_builder.add_memo("swap", &[]);
+        let simulator = TestSimulator::new(Err("Simulation failed".to_string()));
+        let cfg = ComputeUnitEstimateConfig::new(10);
+        assert!(tx_builder
+            .estimate_compute_unit_limit(&simulator, &[], &cfg)
+            .await
+            .is_err());
+        assert_eq!(
+            compute_unit_limit(&tx_builder.all_instructions()),
+            Some(300_000)
+        );
+
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.add_memo("swap", &[]);
+        assert!(tx_builder
+            .estimate_compute_unit_limit(&simulator, &[], &cfg)
+            .await
+            .is_err());
+        assert_eq!(compute_unit_limit(&tx_builder.all_instructions()), None);
+        assert_eq!(
+            tx_builder.all_instruction_types(),
+            vec![InstructionType::Memo]
+        );
+    }
+
+    #[test]
+    fn build_legacy_signs_small_transactions() {
+        let owner = keypair_owner();
+        let fee_payer = keypair_owner();
+        let mut tx_builder = TxBuilder::new_with_fee_payer_signer(&owner, &fee_payer);
+        tx_builder.add_memo("swap", &[owner.pubkey()]);
+        let tx = tx_builder.build_legacy(Hash::new_unique()).unwrap();
+        assert_eq!(tx.message.account_keys[0], fee_payer.pubkey());
+        assert_eq!(tx.signatures.len(), 2);
+        assert!(tx.verify().is_ok());
+    }
+
+    #[test]
+    fn build_legacy_rejects_oversized_transactions() {
+        let owner = keypair_owner();
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        let accounts: Vec<AccountMeta> = (0..40)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect();
+        tx_builder.add_instruction(MakeInstructionsResult::new(
+            vec![Instruction::new_with_bytes(Pubkey::new_unique(), &[], accounts)],
+            vec![InstructionType::Memo],
+        ));
+        let err = tx_builder.build_legacy(Hash::new_unique()).unwrap_err();
+        assert!(err.contains("lookup tables are required"), "{}", err);
     }
 }
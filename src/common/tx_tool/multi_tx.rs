@@ -0,0 +1,217 @@
+use crate::common::owner::Owner;
+use crate::common::tx_tool::unsigned_tx::UnsignedTransaction;
+use crate::common::tx_tool::{compile_v0_message, transaction_size, ComputeBudgetConfig, TxBuilder};
+use crate::raydium::tpe::MakeInstructionsResult;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Packs instruction groups into as many v0 transactions as needed.
+/// A group is never split between transactions, order is preserved.
+pub struct MultiTxBuilder<'a> {
+    owner: &'a Owner,
+    fee_payer: Pubkey,
+    fee_payer_signer: Option<&'a Owner>,
+    groups: Vec<MakeInstructionsResult>,
+    compute_budget_config: Option<ComputeBudgetConfig>,
+}
+
+impl<'a> MultiTxBuilder<'a> {
+    pub fn new(owner: &'a Owner, fee_payer: Pubkey) -> Self {
+        Self {
+            owner,
+            fee_payer,
+            fee_payer_signer: None,
+            groups: vec![],
+            compute_budget_config: None,
+        }
+    }
+    pub fn new_with_fee_payer_signer(owner: &'a Owner, fee_payer: &'a Owner) -> Self {
+        let mut res = Self::new(owner, fee_payer.pubkey());
+        res.fee_payer_signer = Some(fee_payer);
+        res
+    }
+    pub fn add_instruction(&mut self, instruction: MakeInstructionsResult) {
+        self.groups.push(instruction);
+    }
+    /// Applied to every produced transaction.
+    pub fn add_custom_compute_budget(&mut self, cfg: ComputeBudgetConfig) {
+        self.compute_budget_config = Some(cfg);
+    }
+    fn create_tx_builder(&self) -> TxBuilder<'a> {
+        let mut tx_builder = match self.fee_payer_signer {
+            None => TxBuilder::new(self.owner, self.fee_payer),
+            Some(x) => TxBuilder::new_with_fee_payer_signer(self.owner, x),
+        };
+        if let Some(x) = self.compute_budget_config {
+            tx_builder.add_custom_compute_budget(x);
+        }
+        tx_builder
+    }
+    /// Compile errors are returned as is, only size overflow is `false`.
+    fn fits(
+        &self,
+        instructions: &[Instruction],
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<bool, String> {
+        let message = compile_v0_message(
+            &self.fee_payer,
+            instructions,
+            lookup_table_accounts,
+            Hash::default(),
+        )?;
+        Ok(transaction_size(&message) <= PACKET_DATA_SIZE)
+    }
+    /// Splits groups into builders in execution order.
+    pub fn into_tx_builders(
+        mut self,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<Vec<TxBuilder<'a>>, String> {
+        let base_instructions = match &self.compute_budget_config {
+            None => vec![],
+            Some(x) => x.instructions(),
+        };
+        let mut batches: Vec<Vec<MakeInstructionsResult>> = vec![];
+        let mut current: Vec<MakeInstructionsResult> = vec![];
+        let mut current_instructions = base_instructions.clone();
+        let groups = std::mem::take(&mut self.groups);
+        for (i, group) in groups.into_iter().enumerate() {
+            let mut candidate = current_instructions.clone();
+            candidate.extend(group.instructions.iter().cloned());
+            candidate.extend(group.end_instructions.iter().cloned());
+            // Combined groups may overflow account indexes, the group alone decides.
+            if self
+                .fits(&candidate, lookup_table_accounts)
+                .unwrap_or(false)
+            {
+                current_instructions = candidate;
+                current.push(group);
+                continue;
+            }
+            let mut alone = base_instructions.clone();
+            alone.extend(group.instructions.iter().cloned());
+            alone.extend(group.end_instructions.iter().cloned());
+            if !self
+                .fits(&alone, lookup_table_accounts)
+                .map_err(|e| format!("Instruction group {} failed to compile: {}", i, e))?
+            {
+                return Err(format!(
+                    "Instruction group {} does not fit into a single transaction",
+                    i
+                ));
+            }
+            batches.push(std::mem::take(&mut current));
+            current_instructions = alone;
+            current.push(group);
+        }
+        if !current.is_empty() {
+            batches.push(current);
+        }
+        Ok(batches
+            .into_iter()
+            .map(|batch| {
+                let mut tx_builder = self.create_tx_builder();
+                for x in batch {
+                    tx_builder.add_instruction(x);
+                }
+                tx_builder
+            })
+            .collect())
+    }
+    pub fn build_v0_unsigned(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<Vec<UnsignedTransaction>, String> {
+        self.into_tx_builders(lookup_table_accounts)?
+            .into_iter()
+            .map(|x| x.build_v0_unsigned(recent_blockhash, lookup_table_accounts))
+            .collect()
+    }
+    pub fn build_v0(
+        self,
+        recent_blockhash: Hash,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<Vec<VersionedTransaction>, String> {
+        self.into_tx_builders(lookup_table_accounts)?
+            .into_iter()
+            .map(|x| x.build_v0(recent_blockhash, lookup_table_accounts))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::memo::make_memo_instructions;
+    use crate::common::owner::keypair_owner;
+    use crate::common::tx_tool::tx_type::InstructionType;
+
+    fn memo_group(len: usize) -> MakeInstructionsResult {
+        make_memo_instructions(&"m".repeat(len), &[])
+    }
+
+    #[test]
+    fn packs_groups_in_order() {
+        let owner = keypair_owner();
+        let mut multi_tx = MultiTxBuilder::new(&owner, owner.pubkey());
+        for len in [400, 401, 402, 403, 10] {
+            multi_tx.add_instruction(memo_group(len));
+        }
+        let memo_lens: Vec<Vec<usize>> = multi_tx
+            .into_tx_builders(&[])
+            .unwrap()
+            .iter()
+            .map(|x| x.all_instructions().iter().map(|x| x.data.len()).collect())
+            .collect();
+        assert_eq!(memo_lens, vec![vec![400, 401], vec![402, 403, 10]]);
+    }
+
+    #[test]
+    fn compute_budget_goes_into_every_transaction() {
+        let owner = keypair_owner();
+        let mut multi_tx = MultiTxBuilder::new(&owner, owner.pubkey());
+        multi_tx.add_custom_compute_budget(ComputeBudgetConfig::new(200_000, 1));
+        multi_tx.add_instruction(memo_group(700));
+        multi_tx.add_instruction(memo_group(700));
+        let tx_builders = multi_tx.into_tx_builders(&[]).unwrap();
+        assert_eq!(tx_builders.len(), 2);
+        for x in tx_builders {
+            assert_eq!(
+                x.all_instruction_types(),
+                vec![
+                    InstructionType::SetComputeUnitPrice,
+                    InstructionType::SetComputeUnitLimit,
+                    InstructionType::Memo,
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_group_is_rejected() {
+        let owner = keypair_owner();
+        let mut multi_tx = MultiTxBuilder::new(&owner, owner.pubkey());
+        multi_tx.add_instruction(memo_group(10));
+        multi_tx.add_instruction(memo_group(1300));
+        let err = multi_tx.into_tx_builders(&[]).err().unwrap();
+        assert!(err.contains("Instruction group 1 does not fit"), "{}", err);
+    }
+
+    #[test]
+    fn compile_error_is_not_reported_as_size() {
+        let owner = keypair_owner();
+        let signers: Vec<Pubkey> = (0..300).map(|_| Pubkey::new_unique()).collect();
+        let mut multi_tx = MultiTxBuilder::new(&owner, owner.pubkey());
+        multi_tx.add_instruction(make_memo_instructions("m", &signers));
+        let err = multi_tx.into_tx_builders(&[]).err().unwrap();
+        assert!(
+            err.contains("Instruction group 0 failed to compile"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::nonce::state::{State, Versions};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+
+#[derive(Clone, Copy)]
+pub struct DurableNonceConfig {
+    nonce_account: Pubkey,
+    nonce_authority: Pubkey,
+}
+
+impl DurableNonceConfig {
+    pub fn new(nonce_account: Pubkey, nonce_authority: Pubkey) -> Self {
+        Self {
+            nonce_account,
+            nonce_authority,
+        }
+    }
+    pub fn nonce_account(&self) -> Pubkey {
+        self.nonce_account
+    }
+    pub fn nonce_authority(&self) -> Pubkey {
+        self.nonce_authority
+    }
+    pub fn advance_instruction(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.nonce_account, &self.nonce_authority)
+    }
+    /// Stored nonce value, to be used in place of the recent blockhash.
+    pub async fn fetch_blockhash(
+        &self,
+        client: &RpcClient,
+        commitment: CommitmentConfig,
+    ) -> Result<Hash, String> {
+        fetch_nonce_blockhash(client, &self.nonce_account, commitment).await
+    }
+}
+
+pub fn decode_nonce_blockhash(nonce_account: &Pubkey, data: &[u8]) -> Result<Hash, String> {
+    let versions: Versions = bincode::deserialize(data)
+        .map_err(|e| format!("Failed to decode nonce account {}: {}", nonce_account, e))?;
+    match versions.state() {
+        State::Initialized(x) => Ok(x.blockhash()),
+        State::Uninitialized => Err(format!(
+            "Nonce account {} is not initialized",
+            nonce_account
+        )),
+    }
+}
+
+pub async fn fetch_nonce_blockhash(
+    client: &RpcClient,
+    nonce_account: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<Hash, String> {
+    let account = client
+        .get_account_with_commitment(nonce_account, commitment)
+        .await
+        .map_err(|e| e.to_string())?
+        .value
+        .ok_or(format!("Nonce account {} was not found", nonce_account))?;
+    if account.owner != solana_sdk::system_program::ID {
+        return Err(format!(
+            "Account {} is not a nonce account",
+            nonce_account
+        ));
+    }
+    decode_nonce_blockhash(nonce_account, &account.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::owner::keypair_owner;
+    use crate::common::tx_tool::tx_type::InstructionType;
+    use crate::common::tx_tool::{ComputeBudgetConfig, TxBuilder};
+    use solana_sdk::nonce::state::{Data, DurableNonce};
+
+    fn nonce_account_data(state: State) -> Vec<u8> {
+        let mut data = bincode::serialize(&Versions::new(state)).unwrap();
+        data.resize(State::size(), 0);
+        data
+    }
+
+    #[test]
+    fn decodes_initialized_nonce() {
+        let nonce_account = Pubkey::new_unique();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let data = nonce_account_data(State::Initialized(Data::new(
+            Pubkey::new_unique(),
+            durable_nonce,
+            5_000,
+        )));
+        assert_eq!(
+            decode_nonce_blockhash(&nonce_account, &data),
+            Ok(*durable_nonce.as_hash())
+        );
+    }
+
+    #[test]
+    fn rejects_uninitialized_nonce() {
+        let nonce_account = Pubkey::new_unique();
+        let data = nonce_account_data(State::Uninitialized);
+        let err = decode_nonce_blockhash(&nonce_account, &data).unwrap_err();
+        assert!(err.contains("is not initialized"), "{}", err);
+        assert!(decode_nonce_blockhash(&nonce_account, &[1, 2]).is_err());
+    }
+
+    #[test]
+    fn advance_nonce_stays_first() {
+        let owner = keypair_owner();
+        let cfg = DurableNonceConfig::new(Pubkey::new_unique(), owner.pubkey());
+        let mut tx_builder = TxBuilder::new(&owner, owner.pubkey());
+        tx_builder.set_durable_nonce(cfg);
+        tx_builder.add_memo("swap", &[]);
+        tx_builder.add_custom_compute_budget(ComputeBudgetConfig::new(200_000, 1));
+        tx_builder.set_compute_unit_limit(300_000);
+        tx_builder.add_memo("again", &[]);
+        assert_eq!(
+            tx_builder.all_instruction_types(),
+            vec![
+                InstructionType::AdvanceNonce,
+                InstructionType::SetComputeUnitPrice,
+                InstructionType::SetComputeUnitLimit,
+                InstructionType::Memo,
+                InstructionType::Memo,
+            ]
+        );
+        assert_eq!(tx_builder.all_instructions()[0], cfg.advance_instruction());
+        let message = tx_builder.compile_v0(Hash::new_unique(), &[]).unwrap();
+        let first = &message.instructions()[0];
+        assert_eq!(
+            message.static_account_keys()[first.program_id_index as usize],
+            solana_sdk::system_program::ID
+        );
+        assert_eq!(first.data, cfg.advance_instruction().data);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::common::tx_tool::ComputeBudgetConfig;
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+/// RPC limit for `getRecentPrioritizationFees`.
+pub const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+#[async_trait]
+pub trait PrioritizationFeeSource: Send + Sync {
+    /// Micro-lamport fees paid in recent slots by transactions locking `accounts`.
+    async fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>, String>;
+}
+
+#[async_trait]
+impl PrioritizationFeeSource for RpcClient {
+    async fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>, String> {
+        Ok(self
+            .get_recent_prioritization_fees(accounts)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|x| x.prioritization_fee)
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PriorityLevel {
+    Min,
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+    Max,
+}
+
+impl PriorityLevel {
+    pub fn percentile(&self) -> u8 {
+        match self {
+            PriorityLevel::Min => 0,
+            PriorityLevel::Low => 25,
+            PriorityLevel::Medium => 50,
+            PriorityLevel::High => 75,
+            PriorityLevel::VeryHigh => 95,
+            PriorityLevel::Max => 100,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PriorityFeeEstimate {
+    fees: Vec<u64>,
+    min_micro_lamports: u64,
+    max_micro_lamports: u64,
+}
+
+impl PriorityFeeEstimate {
+    /// Nearest-rank percentile of recent fees, clamped to the estimator bounds.
+    pub fn percentile(&self, percentile: u8) -> u64 {
+        let fee = if self.fees.is_empty() {
+            0
+        } else {
+            let rank = (percentile.min(100) as usize * self.fees.len()).div_ceil(100);
+            self.fees[rank.saturating_sub(1)]
+        };
+        fee.clamp(self.min_micro_lamports, self.max_micro_lamports)
+    }
+    pub fn micro_lamports(&self, level: PriorityLevel) -> u64 {
+        self.percentile(level.percentile())
+    }
+    pub fn compute_budget_config(&self, level: PriorityLevel, units: u32) -> ComputeBudgetConfig {
+        ComputeBudgetConfig::new(units, self.micro_lamports(level))
+    }
+    pub fn samples(&self) -> usize {
+        self.fees.len()
+    }
+}
+
+pub struct PriorityFeeEstimator {
+    min_micro_lamports: u64,
+    max_micro_lamports: u64,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(min_micro_lamports: u64, max_micro_lamports: u64) -> Self {
+        Self {
+            min_micro_lamports: min_micro_lamports.min(max_micro_lamports),
+            max_micro_lamports,
+        }
+    }
+    /// Pass writable accounts of the transaction, e.g. `TxBuilder::writable_accounts`.
+    pub async fn estimate(
+        &self,
+        source: &dyn PrioritizationFeeSource,
+        accounts: &[Pubkey],
+    ) -> Result<PriorityFeeEstimate, String> {
+        let accounts = &accounts[..accounts.len().min(MAX_PRIORITIZATION_FEE_ACCOUNTS)];
+        let mut fees = source.recent_prioritization_fees(accounts).await?;
+        fees.sort_unstable();
+        Ok(PriorityFeeEstimate {
+            fees,
+            min_micro_lamports: self.min_micro_lamports,
+            max_micro_lamports: self.max_micro_lamports,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestFeeSource(Vec<u64>);
+
+    #[async_trait]
+    impl PrioritizationFeeSource for TestFeeSource {
+        async fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, String> {
+            Ok(self.0.clone())
+        }
+    }
+
+    async fn estimate(fees: Vec<u64>, min: u64, max: u64) -> PriorityFeeEstimate {
+        PriorityFeeEstimator::new(min, max)
+            .estimate(&TestFeeSource(fees), &[])
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn nearest_rank_percentiles() {
+        let estimate = estimate(vec![50, 10, 40, 20, 30], 0, u64::MAX).await;
+        assert_eq!(estimate.samples(), 5);
+        assert_eq!(estimate.percentile(0), 10);
+        assert_eq!(estimate.percentile(25), 20);
+        assert_eq!(estimate.percentile(50), 30);
+        assert_eq!(estimate.percentile(95), 50);
+        assert_eq!(estimate.percentile(100), 50);
+        assert_eq!(estimate.percentile(u8::MAX), 50);
+    }
+
+    #[tokio::test]
+    async fn single_sample_covers_every_percentile() {
+        let estimate = estimate(vec![7], 0, u64::MAX).await;
+        assert_eq!(estimate.percentile(0), 7);
+        assert_eq!(estimate.percentile(100), 7);
+    }
+
+    #[tokio::test]
+    async fn empty_sample_falls_back_to_min() {
+        let estimate = estimate(vec![], 1_000, 50_000).await;
+        assert_eq!(estimate.samples(), 0);
+        assert_eq!(estimate.percentile(0), 1_000);
+        assert_eq!(estimate.micro_lamports(PriorityLevel::Max), 1_000);
+    }
+
+    #[tokio::test]
+    async fn percentiles_are_clamped_to_bounds() {
+        let estimate = estimate(vec![10, 20, 30, 40, 50], 15, 45).await;
+        assert_eq!(estimate.micro_lamports(PriorityLevel::Min), 15);
+        assert_eq!(estimate.micro_lamports(PriorityLevel::Medium), 30);
+        assert_eq!(estimate.micro_lamports(PriorityLevel::Max), 45);
+    }
+}
@@ -0,0 +1,423 @@
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use std::time::{Duration, Instant};
+
+pub struct SignatureStatus {
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    /// Reached the requested commitment.
+    pub confirmed: bool,
+}
+
+#[derive(Debug)]
+pub enum SendError {
+    /// Preflight simulation rejected the transaction.
+    Preflight(TransactionError),
+    Rpc(String),
+}
+
+#[async_trait]
+pub trait TxSenderRpc: Send + Sync {
+    async fn send_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        skip_preflight: bool,
+    ) -> Result<Signature, SendError>;
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<SignatureStatus>, String>;
+    async fn block_height(&self, commitment: CommitmentConfig) -> Result<u64, String>;
+}
+
+#[async_trait]
+impl TxSenderRpc for RpcClient {
+    async fn send_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        skip_preflight: bool,
+    ) -> Result<Signature, SendError> {
+        self.send_transaction_with_config(
+            tx,
+            RpcSendTransactionConfig {
+                skip_preflight,
+                preflight_commitment: Some(self.commitment().commitment),
+                max_retries: Some(0),
+                ..RpcSendTransactionConfig::default()
+            },
+        )
+        .await
+        .map_err(|e| match e.get_transaction_error() {
+            Some(x) => SendError::Preflight(x),
+            None => SendError::Rpc(e.to_string()),
+        })
+    }
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<SignatureStatus>, String> {
+        let status = self
+            .get_signature_statuses(&[*signature])
+            .await
+            .map_err(|e| e.to_string())?
+            .value
+            .pop()
+            .flatten();
+        Ok(status.map(|x| SignatureStatus {
+            slot: x.slot,
+            confirmed: x.satisfies_commitment(commitment),
+            err: x.err,
+        }))
+    }
+    async fn block_height(&self, commitment: CommitmentConfig) -> Result<u64, String> {
+        self.get_block_height_with_commitment(commitment)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+pub struct SendConfig {
+    pub commitment: CommitmentConfig,
+    pub skip_preflight: bool,
+    pub poll_interval: Duration,
+    pub rebroadcast_interval: Duration,
+    /// Upper bound for transactions without expiry, e.g. durable nonce ones.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            skip_preflight: false,
+            poll_interval: Duration::from_millis(500),
+            rebroadcast_interval: Duration::from_secs(2),
+            timeout: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DecodedProgramError {
+    pub instruction_index: u8,
+    pub program_id: Option<Pubkey>,
+    pub custom_code: Option<u32>,
+    pub error: InstructionError,
+}
+
+impl DecodedProgramError {
+    pub fn decode(tx: &VersionedTransaction, err: &TransactionError) -> Option<Self> {
+        let (instruction_index, error) = match err {
+            TransactionError::InstructionError(i, e) => (*i, e.clone()),
+            _ => return None,
+        };
+        let program_id = tx
+            .message
+            .instructions()
+            .get(instruction_index as usize)
+            .and_then(|x| {
+                tx.message
+                    .static_account_keys()
+                    .get(x.program_id_index as usize)
+            })
+            .cloned();
+        let custom_code = match error {
+            InstructionError::Custom(x) => Some(x),
+            _ => None,
+        };
+        Some(Self {
+            instruction_index,
+            program_id,
+            custom_code,
+            error,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum SendOutcome {
+    Landed {
+        signature: Signature,
+        slot: u64,
+    },
+    Failed {
+        signature: Signature,
+        /// `None` when preflight rejected the transaction.
+        slot: Option<u64>,
+        error: TransactionError,
+        program_error: Option<DecodedProgramError>,
+    },
+    /// Block height passed `last_valid_block_height` without the transaction landing.
+    Expired {
+        signature: Signature,
+    },
+    TimedOut {
+        signature: Signature,
+    },
+}
+
+/// Sends, rebroadcasts and waits for the commitment.
+/// `last_valid_block_height` comes with the blockhash from `get_latest_blockhash_with_commitment`,
+/// pass `None` for durable nonce transactions together with `SendConfig::timeout`.
+pub async fn send_and_confirm(
+    rpc: &dyn TxSenderRpc,
+    tx: &VersionedTransaction,
+    last_valid_block_height: Option<u64>,
+    cfg: &SendConfig,
+) -> Result<SendOutcome, String> {
+    if last_valid_block_height.is_none() && cfg.timeout.is_none() {
+        return Err(
+            "Either last_valid_block_height or SendConfig::timeout is required".to_string(),
+        );
+    }
+    let signature = match rpc.send_transaction(tx, cfg.skip_preflight).await {
+        Ok(x) => x,
+        Err(SendError::Preflight(error)) => {
+            return Ok(SendOutcome::Failed {
+                signature: tx.signatures.first().cloned().unwrap_or_default(),
+                slot: None,
+                program_error: DecodedProgramError::decode(tx, &error),
+                error,
+            })
+        }
+        Err(SendError::Rpc(e)) => return Err(e),
+    };
+    let started_at = Instant::now();
+    let mut last_sent_at = started_at;
+    loop {
+        tokio::time::sleep(cfg.poll_interval).await;
+        if let Some(outcome) = check_status(rpc, tx, &signature, cfg.commitment).await? {
+            return Ok(outcome);
+        }
+        if let Some(last_valid_block_height) = last_valid_block_height {
+            if rpc.block_height(cfg.commitment).await? > last_valid_block_height {
+                return Ok(check_status(rpc, tx, &signature, cfg.commitment)
+                    .await?
+                    .unwrap_or(SendOutcome::Expired { signature }));
+            }
+        }
+        if let Some(timeout) = cfg.timeout {
+            if started_at.elapsed() >= timeout {
+                return Ok(SendOutcome::TimedOut { signature });
+            }
+        }
+        if last_sent_at.elapsed() >= cfg.rebroadcast_interval {
+            // Already accepted once, rebroadcast errors are not fatal.
+            let _ = rpc.send_transaction(tx, true).await;
+            last_sent_at = Instant::now();
+        }
+    }
+}
+
+async fn check_status(
+    rpc: &dyn TxSenderRpc,
+    tx: &VersionedTransaction,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<Option<SendOutcome>, String> {
+    let status = match rpc.signature_status(signature, commitment).await? {
+        None => return Ok(None),
+        Some(x) => x,
+    };
+    Ok(match status.err {
+        Some(error) => Some(SendOutcome::Failed {
+            signature: *signature,
+            slot: Some(status.slot),
+            program_error: DecodedProgramError::decode(tx, &error),
+            error,
+        }),
+        None if status.confirmed => Some(SendOutcome::Landed {
+            signature: *signature,
+            slot: status.slot,
+        }),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct TestSenderRpc {
+        sent: AtomicUsize,
+        preflight_error: Option<TransactionError>,
+        /// Confirmed status with its slot, `None` until it lands.
+        status: Option<(u64, Option<TransactionError>)>,
+        /// Grows by one on every query.
+        block_height: AtomicU64,
+    }
+
+    #[async_trait]
+    impl TxSenderRpc for TestSenderRpc {
+        async fn send_transaction(
+            &self,
+            tx: &VersionedTransaction,
+            _: bool,
+        ) -> Result<Signature, SendError> {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+            match &self.preflight_error {
+                Some(x) => Err(SendError::Preflight(x.clone())),
+                None => Ok(tx.signatures[0]),
+            }
+        }
+        async fn signature_status(
+            &self,
+            _: &Signature,
+            _: CommitmentConfig,
+        ) -> Result<Option<SignatureStatus>, String> {
+            Ok(self.status.clone().map(|(slot, err)| SignatureStatus {
+                slot,
+                err,
+                confirmed: true,
+            }))
+        }
+        async fn block_height(&self, _: CommitmentConfig) -> Result<u64, String> {
+            Ok(self.block_height.fetch_add(1, Ordering::Relaxed))
+        }
+    }
+
+    fn signed_tx(programs: &[Pubkey]) -> VersionedTransaction {
+        let instructions: Vec<Instruction> = programs
+            .iter()
+            .map(|x| Instruction::new_with_bytes(*x, &[], vec![]))
+            .collect();
+        VersionedTransaction {
+            signatures: vec![Signature::from([1u8; 64])],
+            message: VersionedMessage::Legacy(Message::new(
+                &instructions,
+                Some(&Pubkey::new_unique()),
+            )),
+        }
+    }
+
+    fn fast_config() -> SendConfig {
+        SendConfig {
+            poll_interval: Duration::from_millis(1),
+            ..SendConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn requires_an_expiry() {
+        let rpc = TestSenderRpc::default();
+        let tx = VersionedTransaction::default();
+        assert!(send_and_confirm(&rpc, &tx, None, &SendConfig::default())
+            .await
+            .is_err());
+        assert_eq!(rpc.sent.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn times_out_without_block_height() {
+        let rpc = TestSenderRpc::default();
+        let tx = signed_tx(&[]);
+        let cfg = SendConfig {
+            timeout: Some(Duration::from_millis(5)),
+            ..fast_config()
+        };
+        assert!(matches!(
+            send_and_confirm(&rpc, &tx, None, &cfg).await.unwrap(),
+            SendOutcome::TimedOut { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn lands() {
+        let rpc = TestSenderRpc {
+            status: Some((7, None)),
+            ..TestSenderRpc::default()
+        };
+        let tx = signed_tx(&[Pubkey::new_unique()]);
+        match send_and_confirm(&rpc, &tx, Some(100), &fast_config())
+            .await
+            .unwrap()
+        {
+            SendOutcome::Landed { signature, slot } => {
+                assert_eq!(signature, tx.signatures[0]);
+                assert_eq!(slot, 7);
+            }
+            x => panic!("unexpected outcome {:?}", x),
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_with_decoded_custom_code() {
+        let program = Pubkey::new_unique();
+        let tx = signed_tx(&[Pubkey::new_unique(), program]);
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(6001));
+        let landed = TestSenderRpc {
+            status: Some((7, Some(error.clone()))),
+            ..TestSenderRpc::default()
+        };
+        let preflight = TestSenderRpc {
+            preflight_error: Some(error.clone()),
+            ..TestSenderRpc::default()
+        };
+        for (rpc, expected_slot) in [(landed, Some(7)), (preflight, None)] {
+            match send_and_confirm(&rpc, &tx, Some(100), &fast_config())
+                .await
+                .unwrap()
+            {
+                SendOutcome::Failed {
+                    signature,
+                    slot,
+                    error: e,
+                    program_error,
+                } => {
+                    assert_eq!(signature, tx.signatures[0]);
+                    assert_eq!(slot, expected_slot);
+                    assert_eq!(e, error);
+                    let program_error = program_error.unwrap();
+                    assert_eq!(program_error.instruction_index, 1);
+                    assert_eq!(program_error.program_id, Some(program));
+                    assert_eq!(program_error.custom_code, Some(6001));
+                }
+                x => panic!("unexpected outcome {:?}", x),
+            }
+            assert_eq!(rpc.sent.load(Ordering::Relaxed), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn expires_after_last_valid_block_height() {
+        let rpc = TestSenderRpc::default();
+        let tx = signed_tx(&[]);
+        assert!(matches!(
+            send_and_confirm(&rpc, &tx, Some(3), &fast_config())
+                .await
+                .unwrap(),
+            SendOutcome::Expired { .. }
+        ));
+        // Heights 0..=3 are still valid, 4 is past it.
+        assert_eq!(rpc.block_height.load(Ordering::Relaxed), 5);
+        assert_eq!(rpc.sent.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn rebroadcasts_until_expiry() {
+        let rpc = TestSenderRpc::default();
+        let tx = signed_tx(&[]);
+        let cfg = SendConfig {
+            rebroadcast_interval: Duration::ZERO,
+            ..fast_config()
+        };
+        assert!(matches!(
+            send_and_confirm(&rpc, &tx, Some(3), &cfg).await.unwrap(),
+            SendOutcome::Expired { .. }
+        ));
+        // The first send and one rebroadcast per still valid height.
+        assert_eq!(rpc.sent.load(Ordering::Relaxed), 5);
+    }
+}
@@ -1,7 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InstructionType {
     ClmmSwapBaseIn,
 
+    CreateAccount,
+    CreateATA,
+    InitAccount,
+    CloseAccount,
+
     SetComputeUnitPrice,
     SetComputeUnitLimit,
     TransferTip,
+    AdvanceNonce,
+    Memo,
 }
@@ -0,0 +1,120 @@
+use crate::common::owner::Owner;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Compiled message waiting for signatures of `signers`.
+pub struct UnsignedTransaction {
+    message: VersionedMessage,
+    signers: Vec<Pubkey>,
+    signatures: Vec<Signature>,
+}
+
+impl UnsignedTransaction {
+    pub fn new(message: VersionedMessage) -> Self {
+        let num_required_signatures = message.header().num_required_signatures as usize;
+        let signers = message.static_account_keys()[..num_required_signatures].to_vec();
+        Self {
+            message,
+            signatures: vec![Signature::default(); signers.len()],
+            signers,
+        }
+    }
+    /// Keeps valid signatures of `tx`, missing ones stay default.
+    pub fn from_transaction(tx: VersionedTransaction) -> Result<Self, String> {
+        let mut res = Self::new(tx.message);
+        if tx.signatures.len() != res.signers.len() {
+            return Err(format!(
+                "Expected {} signatures, got {}",
+                res.signers.len(),
+                tx.signatures.len()
+            ));
+        }
+        for (signer, signature) in res.signers.clone().iter().zip(tx.signatures.into_iter()) {
+            if signature != Signature::default() {
+                res.add_signature(signer, signature)?;
+            }
+        }
+        Ok(res)
+    }
+    /// Transaction with default signatures in place of the missing ones.
+    pub fn to_partial_transaction(&self) -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: self.signatures.clone(),
+            message: self.message.clone(),
+        }
+    }
+    pub fn message(&self) -> &VersionedMessage {
+        &self.message
+    }
+    /// Bytes to be signed by every required signer.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.message.serialize()
+    }
+    pub fn signers(&self) -> &Vec<Pubkey> {
+        &self.signers
+    }
+    pub fn signatures(&self) -> &Vec<Signature> {
+        &self.signatures
+    }
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.signers
+            .iter()
+            .zip(self.signatures.iter())
+            .filter(|(_, sig)| sig == &&Signature::default())
+            .map(|(x, _)| *x)
+            .collect()
+    }
+    pub fn is_fully_signed(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+    pub fn add_signature(&mut self, signer: &Pubkey, signature: Signature) -> Result<(), String> {
+        let index = self
+            .signers
+            .iter()
+            .position(|x| x == signer)
+            .ok_or(format!("{} is not a required signer", signer))?;
+        if !signature.verify(signer.as_ref(), &self.message_data()) {
+            return Err(format!("Invalid signature for {}", signer));
+        }
+        self.signatures[index] = signature;
+        Ok(())
+    }
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), String> {
+        let signature = signer
+            .try_sign_message(&self.message_data())
+            .map_err(|e| e.to_string())?;
+        self.add_signature(&signer.pubkey(), signature)
+    }
+    /// Signs with every owner that is a required signer, others are skipped.
+    pub async fn sign_with_owners(&mut self, owners: &[&Owner]) -> Result<(), String> {
+        for owner in owners {
+            let pubkey = owner.pubkey();
+            if !self.signers.contains(&pubkey) || !owner.can_sign() {
+                continue;
+            }
+            let signature = owner.sign_message(&self.message_data()).await?;
+            self.add_signature(&pubkey, signature)?;
+        }
+        Ok(())
+    }
+    pub fn into_transaction(self) -> Result<VersionedTransaction, String> {
+        let missing = self.missing_signers();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Missing signatures for {}",
+                missing
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        Ok(VersionedTransaction {
+            signatures: self.signatures,
+            message: self.message,
+        })
+    }
+}
@@ -0,0 +1,11 @@
+use spl_token_2022::solana_program::program_option::COption;
+
+/// Limit of `getMultipleAccounts` per request.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+pub fn coption_to_option<T>(x: COption<T>) -> Option<T> {
+    match x {
+        COption::Some(x) => Some(x),
+        COption::None => None,
+    }
+}
@@ -0,0 +1,123 @@
+use crate::common::pda::get_ata_address;
+use crate::common::pubkey::{ASSOCIATED_TOKEN_PROGRAM_ID, WSOL_MINT};
+use crate::common::tx_tool::tx_type::InstructionType;
+use crate::raydium::tpe::MakeInstructionsResult;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::{system_instruction, system_program};
+
+const CREATE_IDEMPOTENT_DATA: [u8; 1] = [1];
+
+pub struct AccountInstrument {}
+impl AccountInstrument {
+    pub fn create_ata_idempotent_instruction(
+        payer: &Pubkey,
+        ata: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::from_str_const(ASSOCIATED_TOKEN_PROGRAM_ID),
+            &CREATE_IDEMPOTENT_DATA,
+            vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new(*ata, false),
+                AccountMeta::new_readonly(*owner, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+        )
+    }
+    /// Creates the ATA unless it already exists.
+    pub fn make_create_ata_instructions(
+        owner: &Pubkey,
+        payer: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> (Pubkey, MakeInstructionsResult) {
+        let ata = get_ata_address(owner, mint, Some(token_program)).0;
+        (
+            ata,
+            MakeInstructionsResult::new(
+                vec![Self::create_ata_idempotent_instruction(
+                    payer,
+                    &ata,
+                    owner,
+                    mint,
+                    token_program,
+                )],
+                vec![InstructionType::CreateATA],
+            ),
+        )
+    }
+    /// Closes an empty token account of either token program, rent goes to `destination`.
+    pub fn make_close_account_instructions(
+        account: &Pubkey,
+        destination: &Pubkey,
+        owner: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<MakeInstructionsResult, String> {
+        let close_account = spl_token_2022::instruction::close_account(
+            token_program,
+            account,
+            destination,
+            owner,
+            &[],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(MakeInstructionsResult::new(
+            vec![close_account],
+            vec![InstructionType::CloseAccount],
+        ))
+    }
+    /// Temporary WSOL account funded by `owner` with `amount` lamports on top of rent.
+    /// Closed by the end instruction, all lamports go back to `owner`, so a sponsored
+    /// fee payer never funds it. `rent` is the cluster rent, e.g. from `Raydium::fetch_rent`.
+    pub fn make_create_wsol_account_instructions(
+        owner: &Pubkey,
+        amount: u64,
+        rent: &Rent,
+    ) -> Result<(Pubkey, MakeInstructionsResult), String> {
+        let account = Keypair::new();
+        let pubkey = account.pubkey();
+        let wsol_mint = Pubkey::from_str_const(WSOL_MINT);
+        let space = spl_token::state::Account::LEN;
+        let lamports = rent
+            .minimum_balance(space)
+            .checked_add(amount)
+            .ok_or("WSOL amount overflow")?;
+        let create_account = system_instruction::create_account(
+            owner,
+            &pubkey,
+            lamports,
+            space as u64,
+            &spl_token::ID,
+        );
+        let init_account =
+            spl_token::instruction::initialize_account3(&spl_token::ID, &pubkey, &wsol_mint, owner)
+                .map_err(|e| e.to_string())?;
+        let close_account =
+            spl_token::instruction::close_account(&spl_token::ID, &pubkey, owner, owner, &[])
+                .map_err(|e| e.to_string())?;
+        Ok((
+            pubkey,
+            MakeInstructionsResult {
+                signers: vec![account],
+                instructions: vec![create_account, init_account],
+                instruction_types: vec![
+                    InstructionType::CreateAccount,
+                    InstructionType::InitAccount,
+                ],
+                end_instructions: vec![close_account],
+                end_instruction_types: vec![InstructionType::CloseAccount],
+                lookup_table_address: vec![],
+            },
+        ))
+    }
+}
@@ -0,0 +1,73 @@
+use crate::common::owner::Owner;
+use crate::raydium::account::Account;
+use futures::stream::{self, StreamExt};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+pub const DEFAULT_MAX_CONCURRENT_FETCHES: usize = 8;
+
+/// Token accounts of many owners, each kept in its own `Account`.
+pub struct AccountManager {
+    accounts: HashMap<Pubkey, Account>,
+    max_concurrent_fetches: usize,
+}
+
+impl Default for AccountManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_FETCHES)
+    }
+}
+
+impl AccountManager {
+    pub fn new(max_concurrent_fetches: usize) -> Self {
+        Self {
+            accounts: HashMap::new(),
+            max_concurrent_fetches: max_concurrent_fetches.max(1),
+        }
+    }
+    /// Replaces the account of an already managed owner.
+    pub fn add_owner(&mut self, owner: Owner) -> Pubkey {
+        let pubkey = owner.pubkey();
+        self.accounts.insert(pubkey, Account::new(Some(owner)));
+        pubkey
+    }
+    pub fn remove_owner(&mut self, owner: &Pubkey) -> Option<Account> {
+        self.accounts.remove(owner)
+    }
+    pub fn owners(&self) -> Vec<Pubkey> {
+        self.accounts.keys().cloned().collect()
+    }
+    pub fn get(&self, owner: &Pubkey) -> Option<&Account> {
+        self.accounts.get(owner)
+    }
+    pub fn get_mut(&mut self, owner: &Pubkey) -> Option<&mut Account> {
+        self.accounts.get_mut(owner)
+    }
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+    /// Runs `fetch_wallet_token_accounts` for every owner, at most
+    /// `max_concurrent_fetches` at a time. One failed owner does not stop the others.
+    pub async fn fetch_all_token_accounts(
+        &mut self,
+        client: &RpcClient,
+        force_update: bool,
+        commitment: CommitmentConfig,
+    ) -> Vec<(Pubkey, Result<(), String>)> {
+        stream::iter(self.accounts.iter_mut().map(|(owner, account)| async move {
+            let res = account
+                .fetch_wallet_token_accounts(client, force_update, commitment)
+                .await
+                .map(|_| ());
+            (*owner, res)
+        }))
+        .buffer_unordered(self.max_concurrent_fetches)
+        .collect()
+        .await
+    }
+}
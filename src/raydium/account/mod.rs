@@ -1,17 +1,42 @@
+pub mod instrument;
+pub mod manager;
+pub mod subscription;
+pub mod tpe;
 pub mod util;
 
 use crate::common::pda::get_ata_address;
-use crate::raydium::account::util::parse_token_account_resp;
+use crate::raydium::account::subscription::{
+    AccountPubsub, AccountUpdate, BalanceChange, WalletSubscription,
+};
+use crate::common::tx_tool::multi_tx::MultiTxBuilder;
+use crate::common::tx_tool::ComputeBudgetConfig;
+use crate::raydium::account::instrument::AccountInstrument;
+use crate::raydium::account::tpe::{
+    CloseEmptyAccountsResult, TokenAccountRawInfo, TokenAccountState,
+};
+use crate::raydium::account::util::{
+    fetch_token_accounts_by_owner, parse_token_account_data, parse_token_account_resp,
+};
+use crate::raydium::mint::MintRegistry;
+use solana_account_decoder_client_types::UiAccountEncoding;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_client::rpc_request::TokenAccountsFilter;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tokio::join;
 use crate::common::owner::Owner;
 
+pub const DEFAULT_TOKEN_ACCOUNTS_CACHE_TTL: Duration = Duration::from_secs(60);
+
 pub struct Account {
     pub owner: Option<Owner>,
     pub token_accounts: Vec<TokenAccount>,
+    cache_ttl: Duration,
+    fetched_at: Option<Instant>,
+    encoding: UiAccountEncoding,
+    /// Last applied subscription slot per account, older updates are dropped.
+    update_slots: HashMap<Pubkey, u64>,
 }
 #[derive(Debug)]
 pub struct TokenAccount {
@@ -21,6 +46,34 @@ pub struct TokenAccount {
     pub amount: rug::Integer,
     pub is_native: bool,
     pub program_id: Pubkey,
+    /// Full decoded state, `None` for the native SOL entry.
+    pub raw: Option<TokenAccountRawInfo>,
+}
+
+impl TokenAccount {
+    /// Catches client-side what would fail on-chain when the account takes part in a swap.
+    /// A delegate does not block the swap: the owner signs the transfer, which is not limited
+    /// by `delegated_amount`, and the delegate keeps its allowance over what is left.
+    pub fn check_swappable(&self, is_input: bool) -> Result<(), String> {
+        let raw = match &self.raw {
+            None => return Ok(()),
+            Some(x) => x,
+        };
+        let pubkey = self.pubkey.unwrap_or_default();
+        if raw.state == TokenAccountState::Uninitialized {
+            return Err(format!("Token account {} is not initialized", pubkey));
+        }
+        if raw.is_frozen() {
+            return Err(format!("Token account {} is frozen", pubkey));
+        }
+        if is_input && raw.is_cpi_guarded() {
+            return Err(format!(
+                "Token account {} has CPI guard enabled, program transfers are locked",
+                pubkey
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Account {
@@ -28,8 +81,40 @@ impl Account {
         Self {
             owner,
             token_accounts: Vec::new(),
+            cache_ttl: DEFAULT_TOKEN_ACCOUNTS_CACHE_TTL,
+            fetched_at: None,
+            encoding: UiAccountEncoding::JsonParsed,
+            update_slots: HashMap::new(),
         }
     }
+    /// Encoding of token accounts requested by `fetch_wallet_token_accounts`, JsonParsed or
+    /// Base64. Base58 is refused by RPC for accounts over 128 bytes, token accounts included.
+    pub fn set_encoding(&mut self, encoding: UiAccountEncoding) -> Result<(), String> {
+        match encoding {
+            UiAccountEncoding::JsonParsed | UiAccountEncoding::Base64 => {
+                self.encoding = encoding;
+                Ok(())
+            }
+            x => Err(format!("Unsupported token account encoding {:?}", x)),
+        }
+    }
+    pub fn set_cache_ttl(&mut self, ttl: Duration) {
+        self.cache_ttl = ttl;
+    }
+    /// Next `fetch_wallet_token_accounts` goes to RPC, call it after sending a transaction.
+    pub fn invalidate_token_accounts(&mut self) {
+        self.fetched_at = None;
+    }
+    pub fn is_token_accounts_cache_valid(&self) -> bool {
+        self.is_token_accounts_cache_valid_at(Instant::now())
+    }
+    fn is_token_accounts_cache_valid_at(&self, now: Instant) -> bool {
+        self.fetched_at
+            .is_some_and(|x| now.saturating_duration_since(x) < self.cache_ttl)
+    }
+    fn should_fetch_token_accounts(&self, force_update: bool, now: Instant) -> bool {
+        force_update || !self.is_token_accounts_cache_valid_at(now)
+    }
 
     pub fn owner_pubkey(&self) -> Result<Pubkey, String> {
         match &self.owner {
@@ -38,64 +123,700 @@ impl Account {
         }
     }
 
-    // @TODO it's currently force_update only
+    /// Cached for `cache_ttl`, `force_update` skips the cache.
     pub async fn fetch_wallet_token_accounts(
         &mut self,
         client: &RpcClient,
-        /*force_update: bool,*/ commitment: CommitmentConfig,
+        force_update: bool,
+        commitment: CommitmentConfig,
     ) -> Result<&Vec<TokenAccount>, String> {
-        let owner = &self.owner.as_ref().unwrap().pubkey();
+        if !self.should_fetch_token_accounts(force_update, Instant::now()) {
+            return Ok(&self.token_accounts);
+        }
+        let owner = &self.owner_pubkey()?;
         let (sol_account_resp, owner_token_account_resp, owner_token_2022_account_resp) = join!(
             client.get_account_with_commitment(owner, commitment),
-            client.get_token_accounts_by_owner_with_commitment(
+            fetch_token_accounts_by_owner(
+                client,
                 owner,
-                TokenAccountsFilter::ProgramId(spl_token::ID),
-                commitment
+                &spl_token::ID,
+                commitment,
+                self.encoding
             ),
-            client.get_token_accounts_by_owner_with_commitment(
+            fetch_token_accounts_by_owner(
+                client,
                 owner,
-                TokenAccountsFilter::ProgramId(spl_token_2022::ID),
-                commitment
+                &spl_token_2022::ID,
+                commitment,
+                self.encoding
             ),
         );
+        let sol_account_resp = sol_account_resp.map_err(|e| e.to_string())?;
+        let sol_slot = sol_account_resp.context.slot;
         let sol_account_resp = sol_account_resp
-            .map_err(|e| e.to_string())?
             .value
             .ok_or(format!("Account {} was not found.", owner.to_string()))?;
-        let owner_token_account_resp = owner_token_account_resp.map_err(|e| e.to_string())?;
-        let owner_token_2022_account_resp =
-            owner_token_2022_account_resp.map_err(|e| e.to_string())?;
+        let owner_token_account_resp = owner_token_account_resp?;
+        let owner_token_2022_account_resp = owner_token_2022_account_resp?;
+        let token_slot = owner_token_account_resp.context.slot;
+        let token_2022_slot = owner_token_2022_account_resp.context.slot;
         let rpc_token_accounts = owner_token_account_resp
             .value
             .into_iter()
             .chain(owner_token_2022_account_resp.value.into_iter())
             .collect();
         let token_accounts =
-            parse_token_account_resp(owner, Some(sol_account_resp), rpc_token_accounts);
+            parse_token_account_resp(owner, Some(sol_account_resp), rpc_token_accounts)?;
+        // Subscription updates older than the fetched state are dropped by `apply_update`.
+        self.update_slots = token_accounts
+            .iter()
+            .map(|x| match x.pubkey {
+                None => (*owner, sol_slot),
+                Some(pubkey) if x.program_id == spl_token::ID => (pubkey, token_slot),
+                Some(pubkey) => (pubkey, token_2022_slot),
+            })
+            .collect();
         self.token_accounts = token_accounts;
+        self.fetched_at = Some(Instant::now());
         Ok(&self.token_accounts)
     }
+    /// `token_program` defaults to `get_mint_program`, unknown mints are an error.
     pub fn get_token_account(
         &self,
         mint: &Pubkey,
         token_program: Option<&Pubkey>,
         is_associated_only: bool,
-    ) -> Option<&TokenAccount> {
-        let token_program = token_program.unwrap_or_else(|| &spl_token::ID);
-        let ata = self.get_associated_token_account(&mint, Some(token_program));
+        mints: &MintRegistry,
+    ) -> Result<Option<&TokenAccount>, String> {
+        let token_program = self.resolve_mint_program(mint, token_program, mints)?;
+        let ata = self.get_associated_token_account(mint, Some(&token_program), mints)?;
         let mut accs: Vec<&TokenAccount> = self
             .token_accounts
             .iter()
-            .filter(|x| x.mint.eq(&mint) && (!is_associated_only || x.pubkey.eq(&Some(ata))))
+            .filter(|x| {
+                x.mint.eq(mint)
+                    && !x.is_native
+                    && x.program_id.eq(&token_program)
+                    && (!is_associated_only || x.pubkey.eq(&Some(ata)))
+            })
             .collect();
         accs.sort_by_key(|x| &x.amount);
-        accs.last().map(|v| &**v)
+        Ok(accs.last().map(|v| &**v))
     }
     pub fn get_associated_token_account(
         &self,
         mint: &Pubkey,
         program_id: Option<&Pubkey>,
-    ) -> Pubkey {
-        get_ata_address(&self.owner_pubkey().unwrap(), mint, program_id).0
+        mints: &MintRegistry,
+    ) -> Result<Pubkey, String> {
+        let program_id = self.resolve_mint_program(mint, program_id, mints)?;
+        Ok(get_ata_address(&self.owner_pubkey()?, mint, Some(&program_id)).0)
+    }
+    /// Token program of `mint` as seen in fetched token accounts, then in `mints`.
+    pub fn get_mint_program(&self, mint: &Pubkey, mints: &MintRegistry) -> Option<Pubkey> {
+        self.token_accounts
+            .iter()
+            .find(|x| !x.is_native && x.mint.eq(mint))
+            .map(|x| x.program_id)
+            .or_else(|| mints.get_program(mint))
+    }
+    fn resolve_mint_program(
+        &self,
+        mint: &Pubkey,
+        token_program: Option<&Pubkey>,
+        mints: &MintRegistry,
+    ) -> Result<Pubkey, String> {
+        match token_program {
+            Some(x) => Ok(*x),
+            None => self.get_mint_program(mint, mints).ok_or(format!(
+                "Token program of mint {} is unknown, call MintRegistry::fetch_mints",
+                mint
+            )),
+        }
+    }
+    /// Zero-balance token accounts the owner can close. Skips accounts with withheld
+    /// transfer fees, frozen ones and ones with a foreign close authority.
+    pub fn get_empty_token_accounts(&self) -> Result<Vec<&TokenAccount>, String> {
+        let owner = self.owner_pubkey()?;
+        Ok(self
+            .token_accounts
+            .iter()
+            .filter(|x| {
+                let raw = match &x.raw {
+                    None => return false,
+                    Some(raw) => raw,
+                };
+                !x.is_native
+                    && x.pubkey.is_some()
+                    && x.amount == 0
+                    && raw.withheld_amount() == 0
+                    && !raw.is_frozen()
+                    && raw.close_authority.is_none_or(|x| x == owner)
+            })
+            .collect())
+    }
+    /// Closes every account from `get_empty_token_accounts`, rent goes to `destination`
+    /// or the owner. Call `invalidate_token_accounts` once the transactions land.
+    pub fn make_close_empty_token_accounts<'a>(
+        &'a self,
+        fee_payer: Option<&'a Owner>,
+        destination: Option<Pubkey>,
+        compute_budget_config: Option<ComputeBudgetConfig>,
+    ) -> Result<CloseEmptyAccountsResult<'a>, String> {
+        let owner = self.owner.as_ref().ok_or("owner was not set")?;
+        let destination = destination.unwrap_or(owner.pubkey());
+        let mut tx_builder = match fee_payer {
+            None => MultiTxBuilder::new(owner, owner.pubkey()),
+            Some(fee_payer) => MultiTxBuilder::new_with_fee_payer_signer(owner, fee_payer),
+        };
+        if let Some(x) = compute_budget_config {
+            tx_builder.add_custom_compute_budget(x);
+        }
+        let mut accounts = vec![];
+        let mut rent_lamports: u64 = 0;
+        for x in self.get_empty_token_accounts()? {
+            let pubkey = x.pubkey.ok_or("token account without pubkey")?;
+            tx_builder.add_instruction(AccountInstrument::make_close_account_instructions(
+                &pubkey,
+                &destination,
+                &owner.pubkey(),
+                &x.program_id,
+            )?);
+            rent_lamports += x.raw.as_ref().map(|x| x.lamports).unwrap_or_default();
+            accounts.push(pubkey);
+        }
+        Ok(CloseEmptyAccountsResult {
+            accounts,
+            rent_lamports,
+            tx_builder,
+        })
+    }
+    /// Subscribes to the owner SOL account, its token accounts of both token programs
+    /// and each known token account. Call it before `fetch_wallet_token_accounts`:
+    /// updates in between are buffered and the ones older than the fetch are dropped.
+    /// Feed the updates to `apply_update`, or use `next_balance_change`.
+    pub async fn subscribe(
+        &self,
+        pubsub: &dyn AccountPubsub,
+    ) -> Result<WalletSubscription, String> {
+        let owner = self.owner_pubkey()?;
+        let mut subscription = WalletSubscription::new();
+        subscription.subscribe_account(pubsub, &owner).await?;
+        pubsub
+            .subscribe_token_accounts(&spl_token::ID, &owner, subscription.sender())
+            .await?;
+        pubsub
+            .subscribe_token_accounts(&spl_token_2022::ID, &owner, subscription.sender())
+            .await?;
+        self.subscribe_token_accounts(pubsub, &mut subscription)
+            .await?;
+        Ok(subscription)
+    }
+    /// Subscribes token accounts that are not in `subscription` yet,
+    /// e.g. fetched after `subscribe` or created since.
+    pub async fn subscribe_token_accounts(
+        &self,
+        pubsub: &dyn AccountPubsub,
+        subscription: &mut WalletSubscription,
+    ) -> Result<(), String> {
+        for pubkey in self.token_accounts.iter().filter_map(|x| x.pubkey) {
+            subscription.subscribe_account(pubsub, &pubkey).await?;
+        }
+        Ok(())
+    }
+    /// Applies a subscription update, `None` when no balance changed or the update is stale.
+    pub fn apply_update(&mut self, update: AccountUpdate) -> Result<Option<BalanceChange>, String> {
+        let owner = self.owner_pubkey()?;
+        if self
+            .update_slots
+            .get(&update.pubkey)
+            .is_some_and(|x| *x > update.slot)
+        {
+            return Ok(None);
+        }
+        self.update_slots.insert(update.pubkey, update.slot);
+        let index = self.token_accounts.iter().position(|x| {
+            if update.pubkey == owner {
+                x.is_native
+            } else {
+                x.pubkey == Some(update.pubkey)
+            }
+        });
+        let old_amount = index.map(|i| self.token_accounts[i].amount.clone());
+        if update.pubkey == owner {
+            let new_amount = rug::Integer::from(update.lamports);
+            let old_amount = match index {
+                Some(i) => {
+                    let x = &mut self.token_accounts[i];
+                    x.program_id = update.owner;
+                    std::mem::replace(&mut x.amount, new_amount.clone())
+                }
+                None => {
+                    self.token_accounts.push(TokenAccount {
+                        pubkey: None,
+                        mint: Pubkey::default(),
+                        is_associated: None,
+                        amount: new_amount.clone(),
+                        is_native: true,
+                        program_id: update.owner,
+                        raw: None,
+                    });
+                    rug::Integer::new()
+                }
+            };
+            return Ok(Self::balance_change(
+                update.slot,
+                None,
+                Pubkey::default(),
+                old_amount,
+                new_amount,
+            ));
+        }
+        let is_closed = update.lamports == 0
+            || (update.owner != spl_token::ID && update.owner != spl_token_2022::ID);
+        if is_closed {
+            return Ok(self.remove_token_account(index, update.slot));
+        }
+        let token_account = parse_token_account_data(
+            &owner,
+            update.pubkey,
+            update.owner,
+            update.lamports,
+            &update.data,
+        )?;
+        // Transferred away with SetAuthority, no longer part of the wallet.
+        if token_account.raw.as_ref().is_some_and(|x| x.owner != owner) {
+            return Ok(self.remove_token_account(index, update.slot));
+        }
+        let mint = token_account.mint;
+        let new_amount = token_account.amount.clone();
+        match index {
+            Some(i) => self.token_accounts[i] = token_account,
+            None => self.token_accounts.push(token_account),
+        }
+        Ok(Self::balance_change(
+            update.slot,
+            Some(update.pubkey),
+            mint,
+            old_amount.unwrap_or_default(),
+            new_amount,
+        ))
+    }
+    /// Waits for the next update that changes a balance, new token accounts are subscribed
+    /// first. A close landing before its account is subscribed is missed, so reconcile
+    /// from time to time with `fetch_wallet_token_accounts(force_update = true)`.
+    pub async fn next_balance_change(
+        &mut self,
+        pubsub: &dyn AccountPubsub,
+        subscription: &mut WalletSubscription,
+    ) -> Option<Result<BalanceChange, String>> {
+        loop {
+            if let Err(e) = self.subscribe_token_accounts(pubsub, subscription).await {
+                return Some(Err(e));
+            }
+            let update = subscription.next_update().await?;
+            match self.apply_update(update) {
+                Ok(None) => continue,
+                Ok(Some(x)) => return Some(Ok(x)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+    fn remove_token_account(&mut self, index: Option<usize>, slot: u64) -> Option<BalanceChange> {
+        let x = self.token_accounts.remove(index?);
+        Self::balance_change(slot, x.pubkey, x.mint, x.amount, rug::Integer::new())
+    }
+    fn balance_change(
+        slot: u64,
+        pubkey: Option<Pubkey>,
+        mint: Pubkey,
+        old_amount: rug::Integer,
+        new_amount: rug::Integer,
+    ) -> Option<BalanceChange> {
+        if old_amount == new_amount {
+            return None;
+        }
+        Some(BalanceChange {
+            slot,
+            pubkey,
+            mint,
+            old_amount,
+            new_amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::owner::keypair_owner;
+    use crate::raydium::account::tpe::TokenAccountExtension;
+    use crate::raydium::mint::tpe::MintInfo;
+    use async_trait::async_trait;
+    use solana_sdk::system_program;
+    use spl_token::solana_program::program_option::COption;
+    use spl_token::solana_program::program_pack::Pack;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc::UnboundedSender;
+
+    /// Keeps the senders, the test pushes updates through them.
+    #[derive(Default)]
+    struct TestPubsub {
+        accounts: Mutex<HashMap<Pubkey, UnboundedSender<AccountUpdate>>>,
+        programs: Mutex<HashMap<Pubkey, UnboundedSender<AccountUpdate>>>,
+    }
+
+    impl TestPubsub {
+        fn push_account(&self, update: AccountUpdate) {
+            let pubkey = update.pubkey;
+            self.accounts.lock().unwrap()[&pubkey].send(update).unwrap();
+        }
+        fn push_program(&self, update: AccountUpdate) {
+            let program_id = update.owner;
+            self.programs.lock().unwrap()[&program_id]
+                .send(update)
+                .unwrap();
+        }
+    }
+
+    #[async_trait]
+    impl AccountPubsub for TestPubsub {
+        async fn subscribe_account(
+            &self,
+            pubkey: &Pubkey,
+            sender: UnboundedSender<AccountUpdate>,
+        ) -> Result<(), String> {
+            self.accounts.lock().unwrap().insert(*pubkey, sender);
+            Ok(())
+        }
+        async fn subscribe_token_accounts(
+            &self,
+            program_id: &Pubkey,
+            _owner: &Pubkey,
+            sender: UnboundedSender<AccountUpdate>,
+        ) -> Result<(), String> {
+            self.programs.lock().unwrap().insert(*program_id, sender);
+            Ok(())
+        }
+    }
+
+    fn token_update(
+        slot: u64,
+        pubkey: Pubkey,
+        owner: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    ) -> AccountUpdate {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        AccountUpdate {
+            slot,
+            pubkey,
+            lamports: 2_039_280,
+            owner: spl_token::ID,
+            data,
+        }
+    }
+
+    fn closed_update(slot: u64, pubkey: Pubkey) -> AccountUpdate {
+        AccountUpdate {
+            slot,
+            pubkey,
+            lamports: 0,
+            owner: system_program::ID,
+            data: vec![],
+        }
+    }
+
+    /// Wallet with one fetched token account holding 100 at slot 10.
+    fn fetched_account() -> (Account, Pubkey, Pubkey) {
+        let mut account = Account::new(Some(keypair_owner()));
+        let owner = account.owner_pubkey().unwrap();
+        let mint = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let x = token_update(10, pubkey, owner, mint, 100);
+        account
+            .token_accounts
+            .push(parse_token_account_data(&owner, pubkey, x.owner, x.lamports, &x.data).unwrap());
+        account.update_slots.insert(pubkey, 10);
+        (account, pubkey, mint)
+    }
+
+    #[tokio::test]
+    async fn subscription_observes_closes_and_drops_stale_updates() {
+        let (mut account, pubkey, mint) = fetched_account();
+        let owner = account.owner_pubkey().unwrap();
+        let pubsub = TestPubsub::default();
+        let mut subscription = account.subscribe(&pubsub).await.unwrap();
+        assert!(subscription.is_subscribed(&owner));
+        assert!(subscription.is_subscribed(&pubkey));
+        // Older than the fetch, e.g. buffered while fetching.
+        pubsub.push_account(token_update(9, pubkey, owner, mint, 1));
+        // Closes never match the owner filter of the program subscription.
+        pubsub.push_account(closed_update(11, pubkey));
+        let change = account
+            .next_balance_change(&pubsub, &mut subscription)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.slot, 11);
+        assert_eq!(change.pubkey, Some(pubkey));
+        assert_eq!(change.mint, mint);
+        assert_eq!(change.old_amount, 100);
+        assert_eq!(change.new_amount, 0);
+        assert!(account.token_accounts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn new_token_accounts_are_subscribed() {
+        let (mut account, _, _) = fetched_account();
+        let owner = account.owner_pubkey().unwrap();
+        let pubsub = TestPubsub::default();
+        let mut subscription = account.subscribe(&pubsub).await.unwrap();
+        let mint = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        pubsub.push_program(token_update(12, pubkey, owner, mint, 5));
+        let change = account
+            .next_balance_change(&pubsub, &mut subscription)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.pubkey, Some(pubkey));
+        assert_eq!(change.new_amount, 5);
+        assert!(!subscription.is_subscribed(&pubkey));
+        pubsub.push_program(token_update(12, pubkey, owner, mint, 5));
+        pubsub.push_program(token_update(13, pubkey, owner, mint, 7));
+        let change = account
+            .next_balance_change(&pubsub, &mut subscription)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(subscription.is_subscribed(&pubkey));
+        assert_eq!(change.old_amount, 5);
+        assert_eq!(change.new_amount, 7);
+        pubsub.push_account(closed_update(14, pubkey));
+        let change = account
+            .next_balance_change(&pubsub, &mut subscription)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.new_amount, 0);
+        assert_eq!(account.token_accounts.len(), 1);
+    }
+
+    fn token_account(state: TokenAccountState, delegate: Option<Pubkey>) -> TokenAccount {
+        TokenAccount {
+            pubkey: Some(Pubkey::new_unique()),
+            mint: Pubkey::new_unique(),
+            is_associated: Some(true),
+            amount: rug::Integer::from(100),
+            is_native: false,
+            program_id: spl_token_2022::ID,
+            raw: Some(TokenAccountRawInfo {
+                owner: Pubkey::new_unique(),
+                lamports: 0,
+                delegate,
+                delegated_amount: if delegate.is_some() { 50 } else { 0 },
+                close_authority: None,
+                state,
+                is_native: None,
+                extensions: vec![],
+            }),
+        }
+    }
+
+    #[test]
+    fn mint_programs_resolve_from_held_accounts_then_registry() {
+        let (account, pubkey, held_mint) = fetched_account();
+        let owner = account.owner_pubkey().unwrap();
+        let mut mints = MintRegistry::new();
+        assert_eq!(
+            account
+                .get_token_account(&held_mint, None, false, &mints)
+                .unwrap()
+                .unwrap()
+                .pubkey,
+            Some(pubkey)
+        );
+        let mint = Pubkey::new_unique();
+        let err = account
+            .get_associated_token_account(&mint, None, &mints)
+            .err()
+            .unwrap();
+        assert!(err.contains("is unknown"), "{}", err);
+        mints.insert(MintInfo {
+            mint,
+            program_id: spl_token_2022::ID,
+            decimals: 6,
+            supply: 0,
+            mint_authority: None,
+            freeze_authority: None,
+            transfer_fee: None,
+            transfer_hook_program_id: None,
+            permanent_delegate: None,
+            interest_bearing: None,
+            other_extensions: vec![],
+        });
+        assert_eq!(
+            account
+                .get_associated_token_account(&mint, None, &mints)
+                .unwrap(),
+            get_ata_address(&owner, &mint, Some(&spl_token_2022::ID)).0
+        );
+        assert!(account
+            .get_token_account(&mint, None, false, &mints)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn set_encoding_accepts_only_token_account_encodings() {
+        let mut account = Account::new(None);
+        assert!(account.set_encoding(UiAccountEncoding::Base64).is_ok());
+        assert!(account.set_encoding(UiAccountEncoding::JsonParsed).is_ok());
+        for x in [
+            UiAccountEncoding::Base58,
+            UiAccountEncoding::Binary,
+            UiAccountEncoding::Base64Zstd,
+        ] {
+            assert!(account.set_encoding(x).is_err(), "{:?}", x);
+        }
+        assert_eq!(account.encoding, UiAccountEncoding::JsonParsed);
+    }
+
+    #[test]
+    fn check_swappable_rejects_unusable_accounts() {
+        let x = token_account(TokenAccountState::Uninitialized, None);
+        assert!(x.check_swappable(true).is_err());
+        assert!(x.check_swappable(false).is_err());
+        let x = token_account(TokenAccountState::Frozen, None);
+        assert!(x.check_swappable(true).is_err());
+        assert!(x.check_swappable(false).is_err());
+        let mut x = token_account(TokenAccountState::Initialized, None);
+        x.raw.as_mut().unwrap().extensions =
+            vec![TokenAccountExtension::CpiGuard { lock_cpi: true }];
+        assert!(x.check_swappable(true).is_err());
+        assert!(x.check_swappable(false).is_ok());
+    }
+
+    #[test]
+    fn check_swappable_allows_delegated_accounts() {
+        let x = token_account(TokenAccountState::Initialized, Some(Pubkey::new_unique()));
+        assert!(x.check_swappable(true).is_ok());
+        assert!(x.check_swappable(false).is_ok());
+    }
+
+    #[test]
+    fn closes_only_empty_accounts_the_owner_controls() {
+        let mut account = Account::new(Some(keypair_owner()));
+        let owner = account.owner_pubkey().unwrap();
+        let empty = |program_id: Pubkey, lamports: u64| {
+            let mut x = token_account(TokenAccountState::Initialized, None);
+            x.amount = rug::Integer::from(0);
+            x.program_id = program_id;
+            x.raw.as_mut().unwrap().lamports = lamports;
+            x
+        };
+        let spl = empty(spl_token::ID, 2_039_280);
+        let mut token_2022 = empty(spl_token_2022::ID, 2_074_080);
+        token_2022.raw.as_mut().unwrap().close_authority = Some(owner);
+
+        let mut with_balance = empty(spl_token::ID, 1);
+        with_balance.amount = rug::Integer::from(5);
+        let mut withheld = empty(spl_token_2022::ID, 1);
+        withheld.raw.as_mut().unwrap().extensions =
+            vec![TokenAccountExtension::TransferFeeAmount { withheld_amount: 5 }];
+        let mut frozen = empty(spl_token::ID, 1);
+        frozen.raw.as_mut().unwrap().state = TokenAccountState::Frozen;
+        let mut foreign_close_authority = empty(spl_token_2022::ID, 1);
+        foreign_close_authority.raw.as_mut().unwrap().close_authority = Some(Pubkey::new_unique());
+        let mut wsol = empty(spl_token::ID, 1);
+        wsol.is_native = true;
+        let sol = TokenAccount {
+            pubkey: None,
+            mint: Pubkey::default(),
+            is_associated: None,
+            amount: rug::Integer::from(0),
+            is_native: true,
+            program_id: system_program::ID,
+            raw: None,
+        };
+        let closable = [spl.pubkey.unwrap(), token_2022.pubkey.unwrap()];
+        account.token_accounts = vec![
+            spl,
+            with_balance,
+            withheld,
+            token_2022,
+            frozen,
+            foreign_close_authority,
+            wsol,
+            sol,
+        ];
+        assert_eq!(
+            account
+                .get_empty_token_accounts()
+                .unwrap()
+                .iter()
+                .map(|x| x.pubkey.unwrap())
+                .collect::<Vec<Pubkey>>(),
+            closable
+        );
+
+        let res = account
+            .make_close_empty_token_accounts(None, None, None)
+            .unwrap();
+        assert_eq!(res.accounts, closable);
+        assert_eq!(res.rent_lamports, 2_039_280 + 2_074_080);
+        let tx_builders = res.tx_builder.into_tx_builders(&[]).unwrap();
+        assert_eq!(tx_builders.len(), 1);
+        let instructions = tx_builders[0].all_instructions();
+        assert_eq!(instructions.len(), 2);
+        for (instruction, (pubkey, program_id)) in instructions
+            .iter()
+            .zip(closable.iter().zip([spl_token::ID, spl_token_2022::ID]))
+        {
+            assert_eq!(
+                *instruction,
+                spl_token_2022::instruction::close_account(&program_id, pubkey, &owner, &owner, &[])
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn token_accounts_cache_expires_after_ttl() {
+        let mut account = Account::new(None);
+        let now = Instant::now();
+        assert!(!account.is_token_accounts_cache_valid_at(now));
+        assert!(account.should_fetch_token_accounts(false, now));
+
+        account.fetched_at = Some(now);
+        let ttl = DEFAULT_TOKEN_ACCOUNTS_CACHE_TTL;
+        assert!(account.is_token_accounts_cache_valid_at(now));
+        assert!(account.is_token_accounts_cache_valid_at(now + ttl - Duration::from_millis(1)));
+        assert!(!account.is_token_accounts_cache_valid_at(now + ttl));
+        assert!(!account.should_fetch_token_accounts(false, now));
+        assert!(account.should_fetch_token_accounts(true, now));
+        assert!(account.should_fetch_token_accounts(false, now + ttl));
+
+        account.set_cache_ttl(Duration::from_secs(5));
+        assert!(!account.is_token_accounts_cache_valid_at(now + Duration::from_secs(5)));
+        account.set_cache_ttl(Duration::ZERO);
+        assert!(!account.is_token_accounts_cache_valid_at(now));
+
+        account.set_cache_ttl(ttl);
+        account.invalidate_token_accounts();
+        assert!(!account.is_token_accounts_cache_valid());
+        assert!(account.should_fetch_token_accounts(false, now));
     }
 }
@@ -0,0 +1,219 @@
+use crate::raydium::account::util::decode_ui_account_data;
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_account_decoder_client_types::{UiAccount, UiAccountEncoding};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+
+/// Offset of the owner in the token account layout, same for both token programs.
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+
+#[derive(Debug)]
+pub struct AccountUpdate {
+    pub slot: u64,
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+#[async_trait]
+pub trait AccountPubsub: Send + Sync {
+    /// Streams updates of `pubkey` into `sender` until it is closed.
+    async fn subscribe_account(
+        &self,
+        pubkey: &Pubkey,
+        sender: UnboundedSender<AccountUpdate>,
+    ) -> Result<(), String>;
+    /// Streams updates of `program_id` token accounts owned by `owner` into `sender`.
+    async fn subscribe_token_accounts(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        sender: UnboundedSender<AccountUpdate>,
+    ) -> Result<(), String>;
+}
+
+fn to_account_update(
+    slot: u64,
+    pubkey: Pubkey,
+    account: &UiAccount,
+) -> Result<AccountUpdate, String> {
+    Ok(AccountUpdate {
+        slot,
+        pubkey,
+        lamports: account.lamports,
+        owner: Pubkey::from_str(&account.owner).map_err(|e| e.to_string())?,
+        data: decode_ui_account_data(&account.data)?,
+    })
+}
+
+/// `AccountPubsub` over a websocket `PubsubClient`, one task per subscription.
+pub struct PubsubAccountSource {
+    client: Arc<PubsubClient>,
+    commitment: CommitmentConfig,
+}
+
+impl PubsubAccountSource {
+    pub fn new(client: Arc<PubsubClient>, commitment: CommitmentConfig) -> Self {
+        Self { client, commitment }
+    }
+    fn account_config(&self) -> RpcAccountInfoConfig {
+        RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.commitment),
+            ..RpcAccountInfoConfig::default()
+        }
+    }
+}
+
+#[async_trait]
+impl AccountPubsub for PubsubAccountSource {
+    async fn subscribe_account(
+        &self,
+        pubkey: &Pubkey,
+        sender: UnboundedSender<AccountUpdate>,
+    ) -> Result<(), String> {
+        let client = self.client.clone();
+        let pubkey = *pubkey;
+        let config = self.account_config();
+        let (subscribed_tx, subscribed_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, unsubscribe) =
+                match client.account_subscribe(&pubkey, Some(config)).await {
+                    Ok(x) => {
+                        let _ = subscribed_tx.send(Ok(()));
+                        x
+                    }
+                    Err(e) => {
+                        let _ = subscribed_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+            while let Some(x) = stream.next().await {
+                let update = match to_account_update(x.context.slot, pubkey, &x.value) {
+                    Ok(x) => x,
+                    Err(_) => continue,
+                };
+                if sender.send(update).is_err() {
+                    break;
+                }
+            }
+            unsubscribe().await;
+        });
+        subscribed_rx.await.map_err(|e| e.to_string())?
+    }
+    async fn subscribe_token_accounts(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        sender: UnboundedSender<AccountUpdate>,
+    ) -> Result<(), String> {
+        let client = self.client.clone();
+        let program_id = *program_id;
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                TOKEN_ACCOUNT_OWNER_OFFSET,
+                owner.as_ref(),
+            ))]),
+            account_config: self.account_config(),
+            with_context: Some(true),
+            ..RpcProgramAccountsConfig::default()
+        };
+        let (subscribed_tx, subscribed_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, unsubscribe) =
+                match client.program_subscribe(&program_id, Some(config)).await {
+                    Ok(x) => {
+                        let _ = subscribed_tx.send(Ok(()));
+                        x
+                    }
+                    Err(e) => {
+                        let _ = subscribed_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+            while let Some(x) = stream.next().await {
+                let pubkey = match Pubkey::from_str(&x.value.pubkey) {
+                    Ok(x) => x,
+                    Err(_) => continue,
+                };
+                let update = match to_account_update(x.context.slot, pubkey, &x.value.account) {
+                    Ok(x) => x,
+                    Err(_) => continue,
+                };
+                if sender.send(update).is_err() {
+                    break;
+                }
+            }
+            unsubscribe().await;
+        });
+        subscribed_rx.await.map_err(|e| e.to_string())?
+    }
+}
+
+/// Merged updates of the owner SOL account and its token accounts.
+/// Program subscriptions only report accounts that still match the owner filter,
+/// so every known token account also gets its own subscription to observe closes.
+pub struct WalletSubscription {
+    sender: UnboundedSender<AccountUpdate>,
+    receiver: UnboundedReceiver<AccountUpdate>,
+    accounts: HashSet<Pubkey>,
+}
+
+impl WalletSubscription {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Self {
+            sender,
+            receiver,
+            accounts: HashSet::new(),
+        }
+    }
+    pub fn sender(&self) -> UnboundedSender<AccountUpdate> {
+        self.sender.clone()
+    }
+    pub fn is_subscribed(&self, pubkey: &Pubkey) -> bool {
+        self.accounts.contains(pubkey)
+    }
+    /// No-op for accounts that are already subscribed.
+    pub async fn subscribe_account(
+        &mut self,
+        pubsub: &dyn AccountPubsub,
+        pubkey: &Pubkey,
+    ) -> Result<(), String> {
+        if self.accounts.contains(pubkey) {
+            return Ok(());
+        }
+        pubsub.subscribe_account(pubkey, self.sender()).await?;
+        self.accounts.insert(*pubkey);
+        Ok(())
+    }
+    pub async fn next_update(&mut self) -> Option<AccountUpdate> {
+        self.receiver.recv().await
+    }
+}
+
+impl Default for WalletSubscription {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct BalanceChange {
+    pub slot: u64,
+    /// `None` for the native SOL balance.
+    pub pubkey: Option<Pubkey>,
+    pub mint: Pubkey,
+    pub old_amount: rug::Integer,
+    pub new_amount: rug::Integer,
+}
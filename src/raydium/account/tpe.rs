@@ -0,0 +1,70 @@
+use crate::common::tx_tool::multi_tx::MultiTxBuilder;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAccountState {
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenAccountExtension {
+    TransferFeeAmount { withheld_amount: u64 },
+    ImmutableOwner,
+    MemoTransfer { require_incoming_transfer_memos: bool },
+    CpiGuard { lock_cpi: bool },
+    NonTransferableAccount,
+    TransferHookAccount { transferring: bool },
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenAccountRawInfo {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub delegate: Option<Pubkey>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+    pub state: TokenAccountState,
+    /// Rent exempt reserve of native (WSOL) accounts.
+    pub is_native: Option<u64>,
+    pub extensions: Vec<TokenAccountExtension>,
+}
+
+impl TokenAccountRawInfo {
+    pub fn is_frozen(&self) -> bool {
+        self.state == TokenAccountState::Frozen
+    }
+    pub fn withheld_amount(&self) -> u64 {
+        self.extensions
+            .iter()
+            .map(|x| match x {
+                TokenAccountExtension::TransferFeeAmount { withheld_amount } => *withheld_amount,
+                _ => 0,
+            })
+            .sum()
+    }
+    pub fn is_cpi_guarded(&self) -> bool {
+        self.extensions
+            .iter()
+            .any(|x| matches!(x, TokenAccountExtension::CpiGuard { lock_cpi: true }))
+    }
+    pub fn requires_incoming_memo(&self) -> bool {
+        self.extensions.iter().any(|x| {
+            matches!(
+                x,
+                TokenAccountExtension::MemoTransfer {
+                    require_incoming_transfer_memos: true
+                }
+            )
+        })
+    }
+}
+
+pub struct CloseEmptyAccountsResult<'a> {
+    pub accounts: Vec<Pubkey>,
+    /// Lamports held by the closed accounts, transaction fees not subtracted.
+    pub rent_lamports: u64,
+    pub tx_builder: MultiTxBuilder<'a>,
+}
@@ -1,54 +1,406 @@
 use crate::common::pda::get_ata_address;
+use crate::common::util::coption_to_option;
+use crate::raydium::account::tpe::{TokenAccountExtension, TokenAccountRawInfo, TokenAccountState};
 use crate::raydium::account::TokenAccount;
-use solana_account_decoder_client_types::UiAccountData;
-use solana_client::rpc_response::RpcKeyedAccount;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::json;
+use solana_account_decoder_client_types::{ParsedAccount, UiAccountData, UiAccountEncoding};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::{RpcRequest, TokenAccountsFilter};
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::cpi_guard::CpiGuard;
+use spl_token_2022::extension::memo_transfer::MemoTransfer;
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::transfer_hook::TransferHookAccount;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::solana_program::program_error::ProgramError;
+use spl_token_2022::state::AccountState;
 use std::str::FromStr;
 
-// @TODO return tokenAccountRawInfos also
+pub fn decode_ui_account_data(data: &UiAccountData) -> Result<Vec<u8>, String> {
+    match data {
+        UiAccountData::LegacyBinary(x) => bs58::decode(x).into_vec().map_err(|e| e.to_string()),
+        UiAccountData::Binary(x, UiAccountEncoding::Base58 | UiAccountEncoding::Binary) => {
+            bs58::decode(x).into_vec().map_err(|e| e.to_string())
+        }
+        UiAccountData::Binary(x, UiAccountEncoding::Base64) => {
+            STANDARD.decode(x).map_err(|e| e.to_string())
+        }
+        UiAccountData::Binary(_, encoding) => Err(format!("Unsupported encoding {:?}", encoding)),
+        UiAccountData::Json(_) => Err("Account data is json parsed".to_string()),
+    }
+}
+
+fn make_token_account(
+    owner: &Pubkey,
+    acc_pubkey: Pubkey,
+    acc_owner: Pubkey,
+    mint: Pubkey,
+    amount: rug::Integer,
+    raw: TokenAccountRawInfo,
+) -> TokenAccount {
+    TokenAccount {
+        is_associated: Some(
+            get_ata_address(owner, &mint, Some(&acc_owner))
+                .0
+                .eq(&acc_pubkey),
+        ),
+        mint,
+        amount,
+        pubkey: Some(acc_pubkey),
+        program_id: acc_owner,
+        is_native: false,
+        raw: Some(raw),
+    }
+}
+
+fn parse_extensions(
+    state: &StateWithExtensions<spl_token_2022::state::Account>,
+) -> Result<Vec<TokenAccountExtension>, ProgramError> {
+    state
+        .get_extension_types()?
+        .into_iter()
+        .map(|x| {
+            Ok(match x {
+                ExtensionType::TransferFeeAmount => TokenAccountExtension::TransferFeeAmount {
+                    withheld_amount: u64::from(
+                        state.get_extension::<TransferFeeAmount>()?.withheld_amount,
+                    ),
+                },
+                ExtensionType::ImmutableOwner => TokenAccountExtension::ImmutableOwner,
+                ExtensionType::MemoTransfer => TokenAccountExtension::MemoTransfer {
+                    require_incoming_transfer_memos: bool::from(
+                        state
+                            .get_extension::<MemoTransfer>()?
+                            .require_incoming_transfer_memos,
+                    ),
+                },
+                ExtensionType::CpiGuard => TokenAccountExtension::CpiGuard {
+                    lock_cpi: bool::from(state.get_extension::<CpiGuard>()?.lock_cpi),
+                },
+                ExtensionType::NonTransferableAccount => {
+                    TokenAccountExtension::NonTransferableAccount
+                }
+                ExtensionType::TransferHookAccount => TokenAccountExtension::TransferHookAccount {
+                    transferring: bool::from(
+                        state.get_extension::<TransferHookAccount>()?.transferring,
+                    ),
+                },
+                x => TokenAccountExtension::Other(format!("{:?}", x)),
+            })
+        })
+        .collect()
+}
+
+/// Raw SPL Token or Token-2022 account layout, extensions included.
+/// Also usable for accounts read from snapshots.
+pub fn parse_token_account_data(
+    owner: &Pubkey,
+    acc_pubkey: Pubkey,
+    acc_owner: Pubkey,
+    lamports: u64,
+    data: &[u8],
+) -> Result<TokenAccount, String> {
+    if acc_owner != spl_token::ID && acc_owner != spl_token_2022::ID {
+        return Err(format!("Account {} is not a token account", acc_pubkey));
+    }
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(data)
+        .map_err(|e| format!("Failed to decode token account {}: {}", acc_pubkey, e))?;
+    let extensions = parse_extensions(&state)
+        .map_err(|e| format!("Failed to decode extensions of {}: {}", acc_pubkey, e))?;
+    let raw = TokenAccountRawInfo {
+        owner: state.base.owner,
+        lamports,
+        delegate: coption_to_option(state.base.delegate),
+        delegated_amount: state.base.delegated_amount,
+        close_authority: coption_to_option(state.base.close_authority),
+        state: match state.base.state {
+            AccountState::Uninitialized => TokenAccountState::Uninitialized,
+            AccountState::Initialized => TokenAccountState::Initialized,
+            AccountState::Frozen => TokenAccountState::Frozen,
+        },
+        is_native: coption_to_option(state.base.is_native),
+        extensions,
+    };
+    Ok(make_token_account(
+        owner,
+        acc_pubkey,
+        acc_owner,
+        state.base.mint,
+        rug::Integer::from(state.base.amount),
+        raw,
+    ))
+}
+
+fn json_str<'a>(value: &'a serde_json::Value, acc_pubkey: &Pubkey, field: &str) -> Result<&'a str, String> {
+    value
+        .as_str()
+        .ok_or(format!("Token account {} has no {}", acc_pubkey, field))
+}
+
+fn json_pubkey(value: &serde_json::Value) -> Result<Option<Pubkey>, String> {
+    match value.as_str() {
+        None => Ok(None),
+        Some(x) => Pubkey::from_str(x).map(Some).map_err(|e| e.to_string()),
+    }
+}
+
+fn json_amount(value: &serde_json::Value) -> Result<Option<u64>, String> {
+    match value["amount"].as_str() {
+        None => Ok(None),
+        Some(x) => u64::from_str(x).map(Some).map_err(|e| e.to_string()),
+    }
+}
+
+fn json_extension(value: &serde_json::Value) -> TokenAccountExtension {
+    let state = &value["state"];
+    match value["extension"].as_str().unwrap_or_default() {
+        "transferFeeAmount" => TokenAccountExtension::TransferFeeAmount {
+            withheld_amount: state["withheldAmount"].as_u64().unwrap_or_default(),
+        },
+        "immutableOwner" => TokenAccountExtension::ImmutableOwner,
+        "memoTransfer" => TokenAccountExtension::MemoTransfer {
+            require_incoming_transfer_memos: state["requireIncomingTransferMemos"]
+                .as_bool()
+                .unwrap_or_default(),
+        },
+        "cpiGuard" => TokenAccountExtension::CpiGuard {
+            lock_cpi: state["lockCpi"].as_bool().unwrap_or_default(),
+        },
+        "nonTransferableAccount" => TokenAccountExtension::NonTransferableAccount,
+        "transferHookAccount" => TokenAccountExtension::TransferHookAccount {
+            transferring: state["transferring"].as_bool().unwrap_or_default(),
+        },
+        x => TokenAccountExtension::Other(x.to_string()),
+    }
+}
+
+fn parse_token_account_json(
+    owner: &Pubkey,
+    acc_pubkey: Pubkey,
+    acc_owner: Pubkey,
+    lamports: u64,
+    data: &ParsedAccount,
+) -> Result<TokenAccount, String> {
+    let info = &data.parsed["info"];
+    let mint = Pubkey::from_str(json_str(&info["mint"], &acc_pubkey, "mint")?)
+        .map_err(|e| e.to_string())?;
+    let amount = rug::Integer::from_str(json_str(
+        &info["tokenAmount"]["amount"],
+        &acc_pubkey,
+        "amount",
+    )?)
+    .map_err(|e| e.to_string())?;
+    let raw = TokenAccountRawInfo {
+        owner: json_pubkey(&info["owner"])?
+            .ok_or(format!("Token account {} has no owner", acc_pubkey))?,
+        lamports,
+        delegate: json_pubkey(&info["delegate"])?,
+        delegated_amount: json_amount(&info["delegatedAmount"])?.unwrap_or_default(),
+        close_authority: json_pubkey(&info["closeAuthority"])?,
+        state: match json_str(&info["state"], &acc_pubkey, "state")? {
+            "initialized" => TokenAccountState::Initialized,
+            "frozen" => TokenAccountState::Frozen,
+            _ => TokenAccountState::Uninitialized,
+        },
+        is_native: if info["isNative"].as_bool().unwrap_or_default() {
+            Some(json_amount(&info["rentExemptReserve"])?.unwrap_or_default())
+        } else {
+            None
+        },
+        extensions: info["extensions"]
+            .as_array()
+            .map(|x| x.iter().map(json_extension).collect())
+            .unwrap_or_default(),
+    };
+    Ok(make_token_account(owner, acc_pubkey, acc_owner, mint, amount, raw))
+}
+
 pub fn parse_token_account_resp(
     owner: &Pubkey,
     sol_account_resp_opt: Option<solana_account::Account>,
     rpc_token_accounts: Vec<RpcKeyedAccount>,
-) -> Vec<TokenAccount> {
+) -> Result<Vec<TokenAccount>, String> {
     let mut token_accounts: Vec<TokenAccount> = rpc_token_accounts
         .into_iter()
         .map(|e| {
-            let acc_pubkey = Pubkey::from_str(&e.pubkey).unwrap();
-            let acc_owner = Pubkey::from_str(&e.account.owner).unwrap();
-            let data = match e.account.data {
-                UiAccountData::Json(x) => x,
-                _ => unimplemented!(),
-            };
-            let mint = Pubkey::from_str(data.parsed["info"]["mint"].as_str().unwrap()).unwrap();
-            TokenAccount {
-                is_associated: Some(
-                    get_ata_address(owner, &mint, Some(&acc_owner))
-                        .0
-                        .eq(&acc_pubkey),
+            let acc_pubkey = Pubkey::from_str(&e.pubkey).map_err(|e| e.to_string())?;
+            let acc_owner = Pubkey::from_str(&e.account.owner).map_err(|e| e.to_string())?;
+            let lamports = e.account.lamports;
+            match &e.account.data {
+                UiAccountData::Json(x) => {
+                    parse_token_account_json(owner, acc_pubkey, acc_owner, lamports, x)
+                }
+                x => parse_token_account_data(
+                    owner,
+                    acc_pubkey,
+                    acc_owner,
+                    lamports,
+                    &decode_ui_account_data(x)?,
                 ),
-                mint,
-                amount: rug::Integer::from_str(
-                    data.parsed["info"]["tokenAmount"]["amount"]
-                        .as_str()
-                        .unwrap(),
-                )
-                .unwrap(),
-                pubkey: Some(acc_pubkey),
-                program_id: acc_owner,
-                is_native: false,
             }
         })
-        .collect();
+        .collect::<Result<Vec<TokenAccount>, String>>()?;
     if let Some(sol_account_resp) = sol_account_resp_opt {
         token_accounts.push(TokenAccount {
             pubkey: None,
             mint: Pubkey::default(),
             is_associated: None,
-            amount: rug::Integer::from_str(&sol_account_resp.lamports.to_string()).unwrap(),
+            amount: rug::Integer::from(sol_account_resp.lamports),
             is_native: true,
             program_id: sol_account_resp.owner,
+            raw: None,
         });
     }
-    token_accounts
+    Ok(token_accounts)
+}
+
+/// `getTokenAccountsByOwner` with an explicit encoding, base64 is cheaper than jsonParsed.
+/// The context slot is kept to order the result against subscription updates.
+pub async fn fetch_token_accounts_by_owner(
+    client: &RpcClient,
+    owner: &Pubkey,
+    program_id: &Pubkey,
+    commitment: CommitmentConfig,
+    encoding: UiAccountEncoding,
+) -> Result<Response<Vec<RpcKeyedAccount>>, String> {
+    if encoding == UiAccountEncoding::JsonParsed {
+        return client
+            .get_token_accounts_by_owner_with_commitment(
+                owner,
+                TokenAccountsFilter::ProgramId(*program_id),
+                commitment,
+            )
+            .await
+            .map_err(|e| e.to_string());
+    }
+    client
+        .send(
+            RpcRequest::GetTokenAccountsByOwner,
+            json!([
+                owner.to_string(),
+                { "programId": program_id.to_string() },
+                { "encoding": encoding, "commitment": commitment.commitment },
+            ]),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::extension::immutable_owner::ImmutableOwner;
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut};
+    use spl_token_2022::solana_program::program_option::COption;
+    use spl_token_2022::solana_program::program_pack::Pack;
+
+    fn base_account(owner: Pubkey, mint: Pubkey, amount: u64) -> spl_token_2022::state::Account {
+        spl_token_2022::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+    }
+
+    #[test]
+    fn parses_spl_token_layout() {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let acc_pubkey = get_ata_address(&owner, &mint, Some(&spl_token::ID)).0;
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount: 42,
+            delegate: COption::Some(delegate),
+            state: spl_token::state::AccountState::Frozen,
+            is_native: COption::None,
+            delegated_amount: 7,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        let x =
+            parse_token_account_data(&owner, acc_pubkey, spl_token::ID, 2_039_280, &data).unwrap();
+        assert_eq!(x.mint, mint);
+        assert_eq!(x.amount, 42);
+        assert_eq!(x.program_id, spl_token::ID);
+        assert_eq!(x.is_associated, Some(true));
+        let raw = x.raw.unwrap();
+        assert_eq!(raw.owner, owner);
+        assert_eq!(raw.lamports, 2_039_280);
+        assert_eq!(raw.delegate, Some(delegate));
+        assert_eq!(raw.delegated_amount, 7);
+        assert_eq!(raw.state, TokenAccountState::Frozen);
+        assert!(raw.extensions.is_empty());
+    }
+
+    #[test]
+    fn parses_token_2022_layout_with_extensions() {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let acc_pubkey = Pubkey::new_unique();
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+            ExtensionType::TransferFeeAmount,
+            ExtensionType::ImmutableOwner,
+            ExtensionType::CpiGuard,
+        ])
+        .unwrap();
+        let mut data = vec![0u8; len];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(
+                &mut data,
+            )
+            .unwrap();
+        state
+            .init_extension::<TransferFeeAmount>(true)
+            .unwrap()
+            .withheld_amount = 5u64.into();
+        state.init_extension::<ImmutableOwner>(true).unwrap();
+        state.init_extension::<CpiGuard>(true).unwrap().lock_cpi = true.into();
+        state.base = base_account(owner, mint, 1_000);
+        state.pack_base();
+        state.init_account_type().unwrap();
+        let x = parse_token_account_data(&owner, acc_pubkey, spl_token_2022::ID, 0, &data).unwrap();
+        assert_eq!(x.mint, mint);
+        assert_eq!(x.amount, 1_000);
+        assert_eq!(x.program_id, spl_token_2022::ID);
+        assert_eq!(x.is_associated, Some(false));
+        let raw = x.raw.unwrap();
+        assert_eq!(raw.owner, owner);
+        assert_eq!(raw.state, TokenAccountState::Initialized);
+        assert_eq!(
+            raw.extensions,
+            vec![
+                TokenAccountExtension::TransferFeeAmount { withheld_amount: 5 },
+                TokenAccountExtension::ImmutableOwner,
+                TokenAccountExtension::CpiGuard { lock_cpi: true },
+            ]
+        );
+        assert_eq!(raw.withheld_amount(), 5);
+        assert!(raw.is_cpi_guarded());
+    }
+
+    #[test]
+    fn rejects_accounts_of_other_programs() {
+        let data = vec![0u8; spl_token::state::Account::LEN];
+        let owner = Pubkey::new_unique();
+        assert!(parse_token_account_data(
+            &owner,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0,
+            &data
+        )
+        .is_err());
+    }
 }
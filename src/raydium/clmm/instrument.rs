@@ -130,9 +130,11 @@ impl ClmmInstrument {
             Some(get_pda_ex_bitmap_account(&pool_info.program_id, &pool_info.id).0)
         );
         MakeInstructionsResult {
-            // signers: vec![],
+            signers: vec![],
             instructions: vec![swap_instruction],
             instruction_types: vec![InstructionType::ClmmSwapBaseIn],
+            end_instructions: vec![],
+            end_instruction_types: vec![],
             lookup_table_address,
         }
     }
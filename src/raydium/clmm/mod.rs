@@ -1,7 +1,9 @@
 use crate::api::tpe::ClmmKeys;
-use crate::common::owner::OwnerInfo;
+use crate::common::owner::{Owner, OwnerInfo};
 use crate::common::pubkey::WSOL_MINT;
 use crate::common::tx_tool::{ComputeBudgetConfig, TxBuilder, TxTipConfig};
+use crate::raydium::account::instrument::AccountInstrument;
+use crate::raydium::account::Account;
 use crate::raydium::clmm::instrument::ClmmInstrument;
 use crate::raydium::clmm::tpe::ComputeClmmPoolInfo;
 use crate::raydium::clmm::utils::constants::{
@@ -9,6 +11,8 @@ use crate::raydium::clmm::utils::constants::{
 };
 use crate::raydium::clmm::utils::math::SqrtPriceMath;
 use crate::raydium::module_base::ModuleBase;
+use crate::raydium::tpe::MakeInstructionsResult;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::transaction::VersionedTransaction;
 
@@ -25,9 +29,14 @@ impl Clmm {
         Self { base }
     }
 
-    /// @TODO It does not create\fetch token accounts.
-    /// @TODO So, make sure you created it in advance
-    /// @TODO And clmm.base.account.fetch_wallet_token_accounts() was executed.
+    /// Decimals and mint programs come from `Raydium::mints` when fetched there,
+    /// otherwise from `pool_info` and `pool_keys`. Missing token accounts are created as ATAs.
+    /// @TODO It does not fetch token accounts,
+    /// @TODO so make sure clmm.base.account.fetch_wallet_token_accounts() was executed.
+    /// `wallet` picks a wallet from `Raydium::wallets`, `None` uses the default account.
+    /// With `owner_info.use_sol_balance` native SOL of the wallet is wrapped into a temporary
+    /// WSOL account which is closed after the swap, requires `Raydium::fetch_rent`.
+    /// Signs with local signers only, remote signers go through `swap_signed`.
     pub fn swap(
         &self,
         latest_blockhash: solana_hash::Hash,
@@ -40,12 +49,92 @@ impl Clmm {
         remaining_accounts: Vec<Pubkey>,
         price_limit: Option<rug::Float>,
         observation_id: Pubkey,
-        fee_payer: Option<Pubkey>,
+        wallet: Option<&Pubkey>,
+        fee_payer: Option<&Owner>,
         is_associated_only: bool,
         compute_budget_config: Option<ComputeBudgetConfig>,
         tx_tip_config: Option<TxTipConfig>,
+        lookup_table_accounts: &[AddressLookupTableAccount],
     ) -> Result<VersionedTransaction, String> {
-        let mut tx_builder = self.create_tx_builder(fee_payer)?;
+        self.swap_tx_builder(
+            pool_info,
+            prop_pool_keys,
+            input_mint,
+            amount_in,
+            amount_out_min,
+            owner_info,
+            remaining_accounts,
+            price_limit,
+            observation_id,
+            wallet,
+            fee_payer,
+            is_associated_only,
+            compute_budget_config,
+            tx_tip_config,
+        )?
+        .build_v0(latest_blockhash, lookup_table_accounts)
+    }
+    /// Same as `swap` but signs through `Owner::sign_message`, remote signers included.
+    pub async fn swap_signed(
+        &self,
+        latest_blockhash: solana_hash::Hash,
+        pool_info: &ComputeClmmPoolInfo,
+        prop_pool_keys: Option<ClmmKeys>,
+        input_mint: &Pubkey,
+        amount_in: rug::Integer,
+        amount_out_min: rug::Integer,
+        owner_info: OwnerInfo,
+        remaining_accounts: Vec<Pubkey>,
+        price_limit: Option<rug::Float>,
+        observation_id: Pubkey,
+        wallet: Option<&Pubkey>,
+        fee_payer: Option<&Owner>,
+        is_associated_only: bool,
+        compute_budget_config: Option<ComputeBudgetConfig>,
+        tx_tip_config: Option<TxTipConfig>,
+        lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<VersionedTransaction, String> {
+        self.swap_tx_builder(
+            pool_info,
+            prop_pool_keys,
+            input_mint,
+            amount_in,
+            amount_out_min,
+            owner_info,
+            remaining_accounts,
+            price_limit,
+            observation_id,
+            wallet,
+            fee_payer,
+            is_associated_only,
+            compute_budget_config,
+            tx_tip_config,
+        )?
+        .build_v0_signed(latest_blockhash, lookup_table_accounts, &[])
+        .await
+    }
+    /// Same as `swap` but returns the builder, so owners without a keypair
+    /// can use `TxBuilder::build_v0_unsigned` and sign externally.
+    pub fn swap_tx_builder<'a>(
+        &'a self,
+        pool_info: &ComputeClmmPoolInfo,
+        prop_pool_keys: Option<ClmmKeys>,
+        input_mint: &Pubkey,
+        amount_in: rug::Integer,
+        amount_out_min: rug::Integer,
+        owner_info: OwnerInfo,
+        remaining_accounts: Vec<Pubkey>,
+        price_limit: Option<rug::Float>,
+        observation_id: Pubkey,
+        wallet: Option<&Pubkey>,
+        fee_payer: Option<&'a Owner>,
+        is_associated_only: bool,
+        compute_budget_config: Option<ComputeBudgetConfig>,
+        tx_tip_config: Option<TxTipConfig>,
+    ) -> Result<TxBuilder<'a>, String> {
+        let account = self.base.scope.get_account(wallet)?;
+        let mints = &self.base.scope.mints;
+        let mut tx_builder = Self::create_tx_builder(account, fee_payer)?;
         let base_in = input_mint == &pool_info.pool_state.token_mint0;
         let (mint_a_use_sol_balance, mint_b_use_sol_balance) = match owner_info.use_sol_balance {
             None => (false, false),
@@ -64,53 +153,102 @@ impl Clmm {
             } else {
                 SqrtPriceMath::price_to_sqrt_price_x64(
                     price_limit.as_ref().unwrap(),
-                    pool_info.pool_state.mint_decimals0,
-                    pool_info.pool_state.mint_decimals1,
+                    mints
+                        .get_decimals(&pool_info.pool_state.token_mint0)
+                        .unwrap_or(pool_info.pool_state.mint_decimals0),
+                    mints
+                        .get_decimals(&pool_info.pool_state.token_mint1)
+                        .unwrap_or(pool_info.pool_state.mint_decimals1),
                 )
             };
-        let acc_a_is_associated_only = if mint_a_use_sol_balance {
-            false
+        let pool_keys = prop_pool_keys.ok_or("@TODO self.get_clmm_pool_keys")?;
+        let mint_program_a = mints
+            .get_program(&pool_info.pool_state.token_mint0)
+            .unwrap_or(pool_keys.base.mint_program.a);
+        let mint_program_b = mints
+            .get_program(&pool_info.pool_state.token_mint1)
+            .unwrap_or(pool_keys.base.mint_program.b);
+        let amount_in_u64 = amount_in.to_u64().ok_or("amount_in overflows u64")?;
+        let wallet = account.owner_pubkey()?;
+        let mut instructions = MakeInstructionsResult::new(vec![], vec![]);
+        let token_account_a = if mint_a_use_sol_balance {
+            let rent = self.base.scope.rent()?;
+            let (pubkey, wsol_instructions) =
+                AccountInstrument::make_create_wsol_account_instructions(
+                    &wallet,
+                    if base_in { amount_in_u64 } else { 0 },
+                    rent,
+                )?;
+            instructions.append(wsol_instructions);
+            pubkey
         } else {
-            is_associated_only
+            match account
+                .get_token_account(
+                    &pool_info.pool_state.token_mint0,
+                    Some(&mint_program_a),
+                    is_associated_only,
+                    mints,
+                )?
+                .filter(|x| x.pubkey.is_some())
+            {
+                Some(x) => {
+                    x.check_swappable(base_in)?;
+                    x.pubkey.unwrap()
+                }
+                None => {
+                    let (ata, ata_instructions) = AccountInstrument::make_create_ata_instructions(
+                        &wallet,
+                        &tx_builder.fee_payer(),
+                        &pool_info.pool_state.token_mint0,
+                        &mint_program_a,
+                    );
+                    instructions.append(ata_instructions);
+                    ata
+                }
+            }
         };
-        let owner_token_account_a = self
-            .base
-            .scope
-            .account
-            .get_token_account(
-                &pool_info.pool_state.token_mint0,
-                /* @TODO */ None,
-                acc_a_is_associated_only,
-            )
-            .ok_or(format!(
-                "Token account A {} was not found",
-                pool_info.pool_state.token_mint0
-            ))?;
-        let acc_b_is_associated_only = if mint_b_use_sol_balance {
-            false
+        let token_account_b = if mint_b_use_sol_balance {
+            let rent = self.base.scope.rent()?;
+            let (pubkey, wsol_instructions) =
+                AccountInstrument::make_create_wsol_account_instructions(
+                    &wallet,
+                    if base_in { 0 } else { amount_in_u64 },
+                    rent,
+                )?;
+            instructions.append(wsol_instructions);
+            pubkey
         } else {
-            is_associated_only
+            match account
+                .get_token_account(
+                    &pool_info.pool_state.token_mint1,
+                    Some(&mint_program_b),
+                    is_associated_only,
+                    mints,
+                )?
+                .filter(|x| x.pubkey.is_some())
+            {
+                Some(x) => {
+                    x.check_swappable(!base_in)?;
+                    x.pubkey.unwrap()
+                }
+                None => {
+                    let (ata, ata_instructions) = AccountInstrument::make_create_ata_instructions(
+                        &wallet,
+                        &tx_builder.fee_payer(),
+                        &pool_info.pool_state.token_mint1,
+                        &mint_program_b,
+                    );
+                    instructions.append(ata_instructions);
+                    ata
+                }
+            }
         };
-        let owner_token_account_b = self
-            .base
-            .scope
-            .account
-            .get_token_account(
-                &pool_info.pool_state.token_mint1,
-                /* @TODO */ None,
-                acc_b_is_associated_only,
-            )
-            .ok_or(format!(
-                "Token account B {} was not found",
-                pool_info.pool_state.token_mint1
-            ))?;
-        let pool_keys = prop_pool_keys.ok_or("@TODO self.get_clmm_pool_keys")?;
         let owner_info = instrument::OwnerInfo {
-            wallet: self.base.scope.owner_pubkey().unwrap(),
-            token_account_a: owner_token_account_a.pubkey.unwrap(),
-            token_account_b: owner_token_account_b.pubkey.unwrap(),
+            wallet,
+            token_account_a,
+            token_account_b,
         };
-        tx_builder.add_instruction(ClmmInstrument::make_swap_base_in_instructions(
+        instructions.append(ClmmInstrument::make_swap_base_in_instructions(
             pool_info,
             &pool_keys,
             &observation_id,
@@ -121,18 +259,130 @@ impl Clmm {
             sqrt_price_limit_x64,
             remaining_accounts,
         ));
+        tx_builder.add_instruction(instructions);
         if let Some(x) = compute_budget_config {
             tx_builder.add_custom_compute_budget(x);
         }
         if let Some(x) = tx_tip_config {
             tx_builder.add_tip_instruction(x);
         }
-        Ok(tx_builder.build_v0(latest_blockhash))
+        Ok(tx_builder)
     }
-    fn create_tx_builder(&self, fee_payer: Option<Pubkey>) -> Result<TxBuilder, String> {
-        Ok(TxBuilder::new(
-            self.base.scope.account.owner.as_ref().unwrap(),
-            fee_payer.unwrap_or(self.base.scope.owner_pubkey()?.clone()),
-        ))
+    fn create_tx_builder<'a>(
+        account: &'a Account,
+        fee_payer: Option<&'a Owner>,
+    ) -> Result<TxBuilder<'a>, String> {
+        let owner = account.owner.as_ref().ok_or("owner was not set")?;
+        Ok(match fee_payer {
+            None => TxBuilder::new(owner, owner.pubkey()),
+            Some(fee_payer) => TxBuilder::new_with_fee_payer_signer(owner, fee_payer),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tpe::{Base, MintProgram, Vault};
+    use crate::common::owner::keypair_owner;
+    use crate::common::tx_tool::tx_type::InstructionType;
+    use crate::raydium::Raydium;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::program_pack::Pack;
+    use solana_sdk::rent::Rent;
+    use solana_sdk::system_instruction::SystemInstruction;
+
+    fn pool_keys() -> ClmmKeys {
+        ClmmKeys {
+            base: Base {
+                lookup_table_account: None,
+                vault: Vault {
+                    a: Pubkey::new_unique(),
+                    b: Pubkey::new_unique(),
+                },
+                mint_program: MintProgram {
+                    a: spl_token::ID,
+                    b: spl_token::ID,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn use_sol_balance_wraps_with_wallet_funds_under_sponsored_fee_payer() {
+        let wsol_mint = Pubkey::from_str_const(WSOL_MINT);
+        let other_mint = Pubkey::new_unique();
+        let pool_info = ComputeClmmPoolInfo::test_pool(wsol_mint, other_mint);
+        let mut raydium = Raydium::new(Some(keypair_owner()));
+        raydium.rent = Some(Rent::default());
+        let wallet = raydium.owner_pubkey().unwrap();
+        let clmm = Clmm::new(ModuleBase::new(raydium));
+        let fee_payer = keypair_owner();
+        let amount_in = 1_000_000;
+        for base_in in [true, false] {
+            let input_mint = if base_in { wsol_mint } else { other_mint };
+            let tx_builder = clmm
+                .swap_tx_builder(
+                    &pool_info,
+                    Some(pool_keys()),
+                    &input_mint,
+                    rug::Integer::from(amount_in),
+                    rug::Integer::from(0),
+                    OwnerInfo {
+                        use_sol_balance: Some(true),
+                        fee_payer: None,
+                    },
+                    vec![],
+                    None,
+                    Pubkey::new_unique(),
+                    None,
+                    Some(&fee_payer),
+                    false,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let instructions = tx_builder.all_instructions();
+            let instruction_types = tx_builder.all_instruction_types();
+            let position = |instruction_type: InstructionType| {
+                instruction_types
+                    .iter()
+                    .position(|x| *x == instruction_type)
+                    .unwrap()
+            };
+
+            let create = &instructions[position(InstructionType::CreateAccount)];
+            assert_eq!(create.accounts[0].pubkey, wallet);
+            let wsol_account = create.accounts[1].pubkey;
+            let expected_lamports = Rent::default().minimum_balance(spl_token::state::Account::LEN)
+                + if base_in { amount_in } else { 0 };
+            assert!(matches!(
+                bincode::deserialize(&create.data).unwrap(),
+                SystemInstruction::CreateAccount { lamports, .. } if lamports == expected_lamports
+            ));
+
+            let swap = position(InstructionType::ClmmSwapBaseIn);
+            let (input_account, output_account) = (
+                instructions[swap].accounts[3].pubkey,
+                instructions[swap].accounts[4].pubkey,
+            );
+            if base_in {
+                assert_eq!(input_account, wsol_account);
+            } else {
+                assert_eq!(output_account, wsol_account);
+            }
+
+            let close = position(InstructionType::CloseAccount);
+            assert!(close > swap);
+            assert_eq!(instructions[close].accounts[0].pubkey, wsol_account);
+            assert_eq!(instructions[close].accounts[1].pubkey, wallet);
+
+            let tx = tx_builder.build_v0(Hash::new_unique(), &[]).unwrap();
+            let signers = &tx.message.static_account_keys()[..tx.signatures.len()];
+            assert_eq!(signers[0], fee_payer.pubkey());
+            assert!(signers.contains(&wallet));
+            assert!(signers.contains(&wsol_account));
+            assert!(tx.verify_with_results().into_iter().all(|x| x));
+        }
     }
 }
@@ -26,3 +26,94 @@ impl ComputeClmmPoolInfo {
         }
     }
 }
+
+#[cfg(test)]
+impl ComputeClmmPoolInfo {
+    /// Price 1 at tick 0 with tick spacing 1, only the tick array starting at 0 is initialized.
+    pub(crate) fn test_pool(mint0: Pubkey, mint1: Pubkey) -> Self {
+        use carbon_raydium_clmm_decoder::accounts::amm_config::AmmConfig;
+        use carbon_raydium_clmm_decoder::accounts::pool_state::PoolState;
+        use carbon_raydium_clmm_decoder::accounts::tick_array_bitmap_extension::TickArrayBitmapExtension;
+        use carbon_raydium_clmm_decoder::types::RewardInfo;
+        let reward_info = RewardInfo {
+            reward_state: 0,
+            open_time: 0,
+            end_time: 0,
+            last_update_time: 0,
+            emissions_per_second_x64: 0,
+            reward_total_emissioned: 0,
+            reward_claimed: 0,
+            token_mint: Pubkey::default(),
+            token_vault: Pubkey::default(),
+            authority: Pubkey::default(),
+            reward_growth_global_x64: 0,
+        };
+        let id = Pubkey::new_unique();
+        let mut tick_array_bitmap = [0u64; 16];
+        tick_array_bitmap[8] = 1;
+        let pool_state = PoolState {
+            bump: [0],
+            amm_config: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            token_mint0: mint0,
+            token_mint1: mint1,
+            token_vault0: Pubkey::new_unique(),
+            token_vault1: Pubkey::new_unique(),
+            observation_key: Pubkey::new_unique(),
+            mint_decimals0: 6,
+            mint_decimals1: 6,
+            tick_spacing: 1,
+            liquidity: 1_000_000_000_000,
+            sqrt_price_x64: 1u128 << 64,
+            tick_current: 0,
+            padding3: 0,
+            padding4: 0,
+            fee_growth_global0_x64: 0,
+            fee_growth_global1_x64: 0,
+            protocol_fees_token0: 0,
+            protocol_fees_token1: 0,
+            swap_in_amount_token0: 0,
+            swap_out_amount_token1: 0,
+            swap_in_amount_token1: 0,
+            swap_out_amount_token0: 0,
+            status: 0,
+            padding: [0; 7],
+            reward_infos: [reward_info.clone(), reward_info.clone(), reward_info],
+            tick_array_bitmap,
+            total_fees_token0: 0,
+            total_fees_claimed_token0: 0,
+            total_fees_token1: 0,
+            total_fees_claimed_token1: 0,
+            fund_fees_token0: 0,
+            fund_fees_token1: 0,
+            open_time: 0,
+            recent_epoch: 0,
+            padding1: [0; 24],
+            padding2: [0; 32],
+        };
+        let amm_config = AmmConfig {
+            bump: 0,
+            index: 0,
+            owner: Pubkey::new_unique(),
+            protocol_fee_rate: 0,
+            trade_fee_rate: 0,
+            tick_spacing: 1,
+            fund_fee_rate: 0,
+            padding_u32: 0,
+            fund_owner: Pubkey::new_unique(),
+            padding: [0; 3],
+        };
+        let ex_bitmap = TickArrayBitmapExtension {
+            pool_id: id,
+            positive_tick_array_bitmap: [[0; 8]; 14],
+            negative_tick_array_bitmap: [[0; 8]; 14],
+        };
+        Self::new(
+            id,
+            Pubkey::new_unique(),
+            pool_state,
+            Some(ex_bitmap),
+            Some(amm_config),
+        )
+    }
+}
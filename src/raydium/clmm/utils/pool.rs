@@ -7,6 +7,7 @@ use crate::raydium::clmm::utils::tick_array_bitmap::{
     TickArrayBitmap, TickArrayBitmapExtensionUtils,
 };
 use crate::raydium::clmm::utils::tick_query::TickQuery;
+use crate::raydium::mint::MintRegistry;
 use carbon_raydium_clmm_decoder::accounts::tick_array_state::TickArrayState;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
@@ -14,10 +15,14 @@ use std::ops::Neg;
 
 #[derive(Debug)]
 pub struct GetInputAmountAndRemainAccountsResult {
+    /// Transfer fee of the input mint included.
     pub expected_amount_in: rug::Integer,
     pub remaining_accounts: Vec<Pubkey>,
     pub execution_price: u128,
     pub fee_amount: rug::Integer,
+    /// Token-2022 transfer fees on the input and output legs.
+    pub transfer_fee_in: u64,
+    pub transfer_fee_out: u64,
 }
 
 #[derive(Debug)]
@@ -171,9 +176,27 @@ impl PoolUtils {
         tick_array_cache: &HashMap<i32, TickArrayState>,
         output_token_mint: &Pubkey,
         output_amount: rug::Integer,
+        mint_registry: &MintRegistry,
+        epoch: u64,
         // @TODO price_limit: Decimal
     ) -> Result<GetInputAmountAndRemainAccountsResult, String> {
         let zero_for_one = output_token_mint == &pool_info.pool_state.token_mint1;
+        let input_token_mint = if zero_for_one {
+            &pool_info.pool_state.token_mint0
+        } else {
+            &pool_info.pool_state.token_mint1
+        };
+        // The pool has to send the output transfer fee on top of `output_amount`.
+        let transfer_fee_out = match mint_registry.get(output_token_mint) {
+            None => 0,
+            Some(x) => x.inverse_transfer_fee_for(
+                output_amount
+                    .to_u64()
+                    .ok_or("output_amount overflows u64")?,
+                epoch,
+            )?,
+        };
+        let output_amount = output_amount + transfer_fee_out;
         let first_tick_array_start_index =
             Self::get_first_initialized_tick_array(pool_info, zero_for_one)?;
         let mut all_needed_accounts: Vec<Pubkey> =
@@ -202,11 +225,130 @@ impl PoolUtils {
         all_needed_accounts.extend(swap_compute.accounts.into_iter());
         // println!("swap_compute {swap_compute:?}");
         //
+        let transfer_fee_in = match mint_registry.get(input_token_mint) {
+            None => 0,
+            Some(x) => x.inverse_transfer_fee_for(
+                swap_compute
+                    .amount_calculated
+                    .to_u64()
+                    .ok_or("expected_amount_in overflows u64")?,
+                epoch,
+            )?,
+        };
         Ok(GetInputAmountAndRemainAccountsResult {
-            expected_amount_in: swap_compute.amount_calculated,
+            expected_amount_in: swap_compute.amount_calculated + transfer_fee_in,
             remaining_accounts: all_needed_accounts,
             execution_price: swap_compute.sqrt_price_x64,
             fee_amount: swap_compute.fee_amount,
+            transfer_fee_in,
+            transfer_fee_out,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raydium::mint::tpe::MintInfo;
+    use carbon_raydium_clmm_decoder::types::TickState;
+    use spl_token_2022::extension::transfer_fee::{TransferFee, TransferFeeConfig};
+
+    fn tick_state(tick: i32, liquidity_gross: u128) -> TickState {
+        TickState {
+            tick,
+            liquidity_net: 0,
+            liquidity_gross,
+            fee_growth_outside0_x64: 0,
+            fee_growth_outside1_x64: 0,
+            reward_growths_outside_x64: [0; 3],
+            padding: [0; 13],
+        }
+    }
+
+    /// Liquidity up to the last tick of the first array.
+    fn pool(mint0: Pubkey, mint1: Pubkey) -> (ComputeClmmPoolInfo, HashMap<i32, TickArrayState>) {
+        let pool_info = ComputeClmmPoolInfo::test_pool(mint0, mint1);
+        let tick_array = TickArrayState {
+            pool_id: pool_info.id,
+            start_tick_index: 0,
+            ticks: std::array::from_fn(|i| tick_state(i as i32, if i == 59 { 1 } else { 0 })),
+            initialized_tick_count: 1,
+            recent_epoch: 0,
+            padding: [0; 107],
+        };
+        (pool_info, HashMap::from([(0, tick_array)]))
+    }
+
+    fn transfer_fee(epoch: u64, basis_points: u16) -> TransferFee {
+        TransferFee {
+            epoch: epoch.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        }
+    }
+
+    /// 1% until epoch 10, 5% from then on.
+    fn fee_mint(mint: Pubkey) -> MintInfo {
+        MintInfo {
+            mint,
+            program_id: spl_token_2022::ID,
+            decimals: 6,
+            supply: 0,
+            mint_authority: None,
+            freeze_authority: None,
+            transfer_fee: Some(TransferFeeConfig {
+                older_transfer_fee: transfer_fee(0, 100),
+                newer_transfer_fee: transfer_fee(10, 500),
+                ..TransferFeeConfig::default()
+            }),
+            transfer_hook_program_id: None,
+            permanent_delegate: None,
+            interest_bearing: None,
+            other_extensions: vec![],
+        }
+    }
+
+    #[test]
+    fn quote_adds_inverse_transfer_fees_of_the_epoch() {
+        let mint0 = Pubkey::new_unique();
+        let mint1 = Pubkey::new_unique();
+        let (pool_info, tick_arrays) = pool(mint0, mint1);
+        let quote = |output_amount: u64, mints: &MintRegistry, epoch: u64| {
+            PoolUtils::get_input_amount_and_remain_accounts(
+                &pool_info,
+                &tick_arrays,
+                &mint0,
+                rug::Integer::from(output_amount),
+                mints,
+                epoch,
+            )
+            .unwrap()
+        };
+        let plain = quote(1_000_000, &MintRegistry::new(), 0);
+        assert_eq!(plain.transfer_fee_in, 0);
+        assert_eq!(plain.transfer_fee_out, 0);
+
+        let mut mints = MintRegistry::new();
+        mints.insert(fee_mint(mint0));
+        mints.insert(fee_mint(mint1));
+        for (epoch, basis_points) in [(9, 100), (10, 500)] {
+            let fee = transfer_fee(0, basis_points);
+            let x = quote(1_000_000, &mints, epoch);
+            assert_eq!(
+                x.transfer_fee_out,
+                fee.calculate_inverse_fee(1_000_000).unwrap()
+            );
+            // The pool swaps the output plus its fee, the input fee goes on top.
+            let swapped = quote(1_000_000 + x.transfer_fee_out, &MintRegistry::new(), epoch);
+            assert_eq!(
+                x.transfer_fee_in,
+                fee.calculate_inverse_fee(swapped.expected_amount_in.to_u64().unwrap())
+                    .unwrap()
+            );
+            assert_eq!(
+                x.expected_amount_in,
+                swapped.expected_amount_in + x.transfer_fee_in
+            );
+        }
+    }
+}
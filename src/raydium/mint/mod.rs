@@ -0,0 +1,69 @@
+pub mod tpe;
+pub mod util;
+
+use crate::common::util::MAX_MULTIPLE_ACCOUNTS;
+use crate::raydium::mint::tpe::MintInfo;
+use crate::raydium::mint::util::parse_mint_data;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Decoded mints by address. Supply is as of the last fetch.
+#[derive(Default)]
+pub struct MintRegistry {
+    mints: HashMap<Pubkey, MintInfo>,
+}
+
+impl MintRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn get(&self, mint: &Pubkey) -> Option<&MintInfo> {
+        self.mints.get(mint)
+    }
+    pub fn insert(&mut self, info: MintInfo) {
+        self.mints.insert(info.mint, info);
+    }
+    pub fn remove(&mut self, mint: &Pubkey) -> Option<MintInfo> {
+        self.mints.remove(mint)
+    }
+    pub fn get_decimals(&self, mint: &Pubkey) -> Option<u8> {
+        self.get(mint).map(|x| x.decimals)
+    }
+    pub fn get_program(&self, mint: &Pubkey) -> Option<Pubkey> {
+        self.get(mint).map(|x| x.program_id)
+    }
+    /// Fetches mints missing from the registry in batches, all of them with `force_update`.
+    /// Returns infos in the order of `mints`.
+    pub async fn fetch_mints(
+        &mut self,
+        client: &RpcClient,
+        mints: &[Pubkey],
+        force_update: bool,
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<&MintInfo>, String> {
+        let mut missing: Vec<Pubkey> = mints
+            .iter()
+            .filter(|x| force_update || !self.mints.contains_key(x))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = client
+                .get_multiple_accounts_with_commitment(chunk, commitment)
+                .await
+                .map_err(|e| e.to_string())?
+                .value;
+            for (mint, account) in chunk.iter().zip(accounts.into_iter()) {
+                let account = account.ok_or(format!("Mint {} was not found", mint))?;
+                self.insert(parse_mint_data(*mint, account.owner, &account.data)?);
+            }
+        }
+        mints
+            .iter()
+            .map(|x| self.get(x).ok_or(format!("Mint {} was not found", x)))
+            .collect()
+    }
+}
@@ -0,0 +1,56 @@
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestBearingInfo {
+    pub rate_authority: Option<Pubkey>,
+    pub initialization_timestamp: i64,
+    pub pre_update_average_rate: i16,
+    pub last_update_timestamp: i64,
+    /// Basis points per year.
+    pub current_rate: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct MintInfo {
+    pub mint: Pubkey,
+    /// spl_token or spl_token_2022.
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    pub supply: u64,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    pub transfer_fee: Option<TransferFeeConfig>,
+    pub transfer_hook_program_id: Option<Pubkey>,
+    pub permanent_delegate: Option<Pubkey>,
+    pub interest_bearing: Option<InterestBearingInfo>,
+    /// Other Token-2022 extensions by name.
+    pub other_extensions: Vec<String>,
+}
+
+impl MintInfo {
+    /// Fee withheld when `amount` is transferred during `epoch`.
+    pub fn transfer_fee_for(&self, amount: u64, epoch: u64) -> Result<u64, String> {
+        match &self.transfer_fee {
+            None => Ok(0),
+            Some(x) => x
+                .get_epoch_fee(epoch)
+                .calculate_fee(amount)
+                .ok_or(format!("Transfer fee of mint {} overflows", self.mint)),
+        }
+    }
+    /// Fee to add so that `post_fee_amount` arrives during `epoch`.
+    pub fn inverse_transfer_fee_for(
+        &self,
+        post_fee_amount: u64,
+        epoch: u64,
+    ) -> Result<u64, String> {
+        match &self.transfer_fee {
+            None => Ok(0),
+            Some(x) => x
+                .get_epoch_fee(epoch)
+                .calculate_inverse_fee(post_fee_amount)
+                .ok_or(format!("Transfer fee of mint {} overflows", self.mint)),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::common::util::coption_to_option;
+use crate::raydium::mint::tpe::{InterestBearingInfo, MintInfo};
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::interest_bearing_mint::InterestBearingConfig;
+use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::transfer_hook::TransferHook;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::solana_program::program_error::ProgramError;
+
+/// Raw SPL Token or Token-2022 mint layout, extensions included.
+pub fn parse_mint_data(mint: Pubkey, program_id: Pubkey, data: &[u8]) -> Result<MintInfo, String> {
+    if program_id != spl_token::ID && program_id != spl_token_2022::ID {
+        return Err(format!("Account {} is not a token mint", mint));
+    }
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)
+        .map_err(|e| format!("Failed to decode mint {}: {}", mint, e))?;
+    let mut info = MintInfo {
+        mint,
+        program_id,
+        decimals: state.base.decimals,
+        supply: state.base.supply,
+        mint_authority: coption_to_option(state.base.mint_authority),
+        freeze_authority: coption_to_option(state.base.freeze_authority),
+        transfer_fee: None,
+        transfer_hook_program_id: None,
+        permanent_delegate: None,
+        interest_bearing: None,
+        other_extensions: vec![],
+    };
+    parse_extensions(&state, &mut info)
+        .map_err(|e| format!("Failed to decode extensions of mint {}: {}", mint, e))?;
+    Ok(info)
+}
+
+fn parse_extensions(
+    state: &StateWithExtensions<spl_token_2022::state::Mint>,
+    info: &mut MintInfo,
+) -> Result<(), ProgramError> {
+    for x in state.get_extension_types()? {
+        match x {
+            ExtensionType::TransferFeeConfig => {
+                info.transfer_fee = Some(*state.get_extension::<TransferFeeConfig>()?);
+            }
+            ExtensionType::TransferHook => {
+                info.transfer_hook_program_id =
+                    Option::<Pubkey>::from(state.get_extension::<TransferHook>()?.program_id);
+            }
+            ExtensionType::PermanentDelegate => {
+                info.permanent_delegate =
+                    Option::<Pubkey>::from(state.get_extension::<PermanentDelegate>()?.delegate);
+            }
+            ExtensionType::InterestBearingConfig => {
+                let config = state.get_extension::<InterestBearingConfig>()?;
+                info.interest_bearing = Some(InterestBearingInfo {
+                    rate_authority: Option::<Pubkey>::from(config.rate_authority),
+                    initialization_timestamp: i64::from(config.initialization_timestamp),
+                    pre_update_average_rate: i16::from(config.pre_update_average_rate),
+                    last_update_timestamp: i64::from(config.last_update_timestamp),
+                    current_rate: i16::from(config.current_rate),
+                });
+            }
+            x => info.other_extensions.push(format!("{:?}", x)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut};
+    use spl_token_2022::solana_program::program_option::COption;
+    use spl_token_2022::solana_program::program_pack::Pack;
+
+    fn base_mint(authority: Pubkey) -> spl_token_2022::state::Mint {
+        spl_token_2022::state::Mint {
+            mint_authority: COption::Some(authority),
+            supply: 1_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+    }
+
+    #[test]
+    fn parses_spl_token_mint() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0u8; spl_token_2022::state::Mint::LEN];
+        base_mint(authority).pack_into_slice(&mut data);
+        let x = parse_mint_data(mint, spl_token::ID, &data).unwrap();
+        assert_eq!(x.mint, mint);
+        assert_eq!(x.program_id, spl_token::ID);
+        assert_eq!(x.decimals, 6);
+        assert_eq!(x.supply, 1_000_000);
+        assert_eq!(x.mint_authority, Some(authority));
+        assert_eq!(x.freeze_authority, None);
+        assert!(x.transfer_fee.is_none());
+        assert!(x.other_extensions.is_empty());
+        assert!(parse_mint_data(mint, Pubkey::new_unique(), &data).is_err());
+    }
+
+    #[test]
+    fn parses_token_2022_mint_extensions() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let hook_program = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::TransferHook,
+            ExtensionType::PermanentDelegate,
+            ExtensionType::InterestBearingConfig,
+            ExtensionType::MintCloseAuthority,
+        ])
+        .unwrap();
+        let mut data = vec![0u8; len];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+                .unwrap();
+        let fee = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        fee.older_transfer_fee.epoch = 0u64.into();
+        fee.older_transfer_fee.maximum_fee = 1_000u64.into();
+        fee.older_transfer_fee.transfer_fee_basis_points = 100u16.into();
+        fee.newer_transfer_fee.epoch = 10u64.into();
+        fee.newer_transfer_fee.maximum_fee = 5_000u64.into();
+        fee.newer_transfer_fee.transfer_fee_basis_points = 250u16.into();
+        state.init_extension::<TransferHook>(true).unwrap().program_id =
+            Some(hook_program).try_into().unwrap();
+        state.init_extension::<PermanentDelegate>(true).unwrap().delegate =
+            Some(delegate).try_into().unwrap();
+        let interest = state
+            .init_extension::<InterestBearingConfig>(true)
+            .unwrap();
+        interest.rate_authority = Some(authority).try_into().unwrap();
+        interest.initialization_timestamp = 1_700_000_000i64.into();
+        interest.pre_update_average_rate = 50i16.into();
+        interest.last_update_timestamp = 1_700_086_400i64.into();
+        interest.current_rate = (-25i16).into();
+        state
+            .init_extension::<spl_token_2022::extension::mint_close_authority::MintCloseAuthority>(
+                true,
+            )
+            .unwrap();
+        state.base = base_mint(authority);
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let x = parse_mint_data(mint, spl_token_2022::ID, &data).unwrap();
+        assert_eq!(x.program_id, spl_token_2022::ID);
+        assert_eq!(x.decimals, 6);
+        let fee = x.transfer_fee.unwrap();
+        assert_eq!(u16::from(fee.get_epoch_fee(9).transfer_fee_basis_points), 100);
+        assert_eq!(u16::from(fee.get_epoch_fee(10).transfer_fee_basis_points), 250);
+        assert_eq!(x.transfer_fee_for(10_000, 9), Ok(100));
+        assert_eq!(x.transfer_fee_for(10_000, 10), Ok(250));
+        assert_eq!(x.transfer_fee_for(10_000_000, 10), Ok(5_000));
+        assert_eq!(x.transfer_hook_program_id, Some(hook_program));
+        assert_eq!(x.permanent_delegate, Some(delegate));
+        assert_eq!(
+            x.interest_bearing,
+            Some(InterestBearingInfo {
+                rate_authority: Some(authority),
+                initialization_timestamp: 1_700_000_000,
+                pre_update_average_rate: 50,
+                last_update_timestamp: 1_700_086_400,
+                current_rate: -25,
+            })
+        );
+        assert_eq!(x.other_extensions, vec!["MintCloseAuthority".to_string()]);
+    }
+}
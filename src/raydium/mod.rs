@@ -1,23 +1,64 @@
 use crate::common::owner::Owner;
+use crate::raydium::account::manager::AccountManager;
 use crate::raydium::account::Account;
+use crate::raydium::mint::MintRegistry;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::sysvar;
 
 pub mod account;
 pub mod clmm;
+pub mod mint;
 pub mod module_base;
 pub mod tpe;
 
 pub struct Raydium {
     pub account: Account,
+    /// Additional wallets, targeted by `wallet` in module operations.
+    pub wallets: AccountManager,
+    pub mints: MintRegistry,
+    /// Cluster rent, set directly or with `fetch_rent`.
+    pub rent: Option<Rent>,
 }
 
 impl Raydium {
     pub fn new(owner: Option<Owner>) -> Self {
         Self {
             account: Account::new(owner),
+            wallets: AccountManager::default(),
+            mints: MintRegistry::new(),
+            rent: None,
         }
     }
     pub fn owner_pubkey(&self) -> Result<Pubkey, String> {
         self.account.owner_pubkey()
     }
+    /// `None` is the default `account`, otherwise a wallet from `wallets`.
+    pub fn get_account(&self, wallet: Option<&Pubkey>) -> Result<&Account, String> {
+        match wallet {
+            None => Ok(&self.account),
+            Some(x) if self.account.owner_pubkey().is_ok_and(|owner| owner == *x) => {
+                Ok(&self.account)
+            }
+            Some(x) => self
+                .wallets
+                .get(x)
+                .ok_or(format!("Wallet {} is not managed", x)),
+        }
+    }
+    pub fn rent(&self) -> Result<&Rent, String> {
+        self.rent
+            .as_ref()
+            .ok_or("Rent was not fetched, call Raydium::fetch_rent".to_string())
+    }
+    pub async fn fetch_rent(&mut self, client: &RpcClient) -> Result<&Rent, String> {
+        let account = client
+            .get_account(&sysvar::rent::ID)
+            .await
+            .map_err(|e| e.to_string())?;
+        let rent: Rent = bincode::deserialize(&account.data)
+            .map_err(|e| format!("Failed to decode rent sysvar: {}", e))?;
+        Ok(self.rent.insert(rent))
+    }
 }
@@ -1,10 +1,40 @@
 use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Keypair;
 use crate::common::tx_tool::tx_type::InstructionType;
 
 pub struct MakeInstructionsResult {
-    // pub signers: Vec<()>,
+    pub signers: Vec<Keypair>,
     pub instructions: Vec<Instruction>,
     pub instruction_types: Vec<InstructionType>,
+    pub end_instructions: Vec<Instruction>,
+    pub end_instruction_types: Vec<InstructionType>,
     // pub address: Option<T>,
     pub lookup_table_address: Vec<String>,
-}
\ No newline at end of file
+}
+
+impl MakeInstructionsResult {
+    pub fn new(instructions: Vec<Instruction>, instruction_types: Vec<InstructionType>) -> Self {
+        Self {
+            signers: vec![],
+            instructions,
+            instruction_types,
+            end_instructions: vec![],
+            end_instruction_types: vec![],
+            lookup_table_address: vec![],
+        }
+    }
+    /// Appends `other` keeping its end instructions after the current ones.
+    pub fn append(&mut self, mut other: MakeInstructionsResult) {
+        self.signers.append(&mut other.signers);
+        self.instructions.append(&mut other.instructions);
+        self.instruction_types.append(&mut other.instruction_types);
+        self.end_instructions.append(&mut other.end_instructions);
+        self.end_instruction_types
+            .append(&mut other.end_instruction_types);
+        for x in other.lookup_table_address {
+            if !self.lookup_table_address.contains(&x) {
+                self.lookup_table_address.push(x);
+            }
+        }
+    }
+}